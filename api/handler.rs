@@ -1,338 +1,5823 @@
-use moka::future::Cache;
+//! Vercel serverless handler proxying the Fantasy Premier League API.
+//!
+//! The upstream timeout budget is read from `FPL_TIMEOUT_SECS` (default 10 s)
+//! when the HTTP client is first built. The primary FPL request gets at most
+//! 5 s of it and the backup URL gets whatever remains.
+//!
+//! When `KV_URL` is set, cache entries are also written to Redis so they
+//! survive cold starts and are shared between instances; lookups try the
+//! in-process cache first, then Redis, then upstream.
+
+mod router;
+
+use bytes::Bytes;
+use fantasy_pl_vercel_proxy_rs::{
+    kv::KvCache,
+    metrics,
+    model::{BootstrapStatic, Chip, EntryPicks, Event, Fixture, LiveEvent, Pick, Player},
+    tls,
+};
+use flate2::{write::GzEncoder, Compression};
+use futures::stream::{FuturesUnordered, StreamExt};
+use http::{response::Builder, HeaderMap, HeaderValue, Method};
+use moka::{future::Cache, Expiry};
 use reqwest::Client;
-use serde_json::{json, Value};
-use std::{sync::OnceLock, time::Duration};
-use tracing::{error, info, warn};
+use router::{BoxedFuture, RouteContext, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
+    future::Future,
+    io::Write,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        Arc, OnceLock,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::{OnceCell, Semaphore, SemaphorePermit};
+use tracing::{error, field, info, info_span, warn, Instrument, Span};
+use uuid::Uuid;
 use vercel_runtime::{run, Body, Error, Request, Response};
 
-// Configuration constants
-const FPL_API_BASE: &str = "https://fantasy.premierleague.com/api";
-const BACKUP_API_BASE: &str = "https://fpl-static-data.vercel.app";
+// Configuration constants; the upstream bases can be overridden through the
+// FPL_API_BASE and BACKUP_API_BASE environment variables
+const DEFAULT_FPL_API_BASE: &str = "https://fantasy.premierleague.com/api";
+const DEFAULT_BACKUP_API_BASE: &str = "https://fpl-static-data.vercel.app";
 const BACKUP_SEASON: &str = "2025-2026";
+// Sent as X-Proxy-Version so operators can tell which build answered
+const PROXY_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Upstream timeouts in seconds; the total is overridable via FPL_TIMEOUT_SECS
+const DEFAULT_UPSTREAM_TIMEOUT: u64 = 10;
+const PRIMARY_REQUEST_TIMEOUT: u64 = 5;
+
+// How long cold-start cache warm-up may run before it is reported as late
+const WARM_UP_DEADLINE: u64 = 5; // seconds
+
+// Requests handled at once; shutdown drains them by taking every permit
+const MAX_IN_FLIGHT_REQUESTS: u32 = 1_024;
+
+// How long Ctrl-C waits for in-flight requests before exiting anyway
+const SHUTDOWN_GRACE_PERIOD: u64 = 10; // seconds
+
+// Asks every crawler to stay away, so bots do not spend upstream quota
+const ROBOTS_TXT: &str = "User-agent: *\nDisallow: /\n";
+
+// 1x1 fully transparent icon: the ICO header and directory entry pointing at
+// a 32-bit BMP with one BGRA pixel and its AND-mask row. No ICO is smaller
+// than 22 bytes of header and directory, so this is 70 bytes, not 16.
+const FAVICON_ICO: [u8; 70] = [
+    0, 0, 1, 0, 1, 0, // ICONDIR: reserved, type 1 (icon), 1 image
+    1, 1, 0, 0, 1, 0, 32, 0, 48, 0, 0, 0, 22, 0, 0, 0, // 1x1, 32 bpp, 48 bytes at offset 22
+    40, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 1, 0, 32, 0, // BITMAPINFOHEADER, height doubled for the mask
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, // the pixel, alpha 0
+    0x80, 0, 0, 0, // AND mask row, padded to 32 bits: transparent
+];
+
+// Longest client-supplied X-Request-ID that is reused rather than replaced
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+// Timeout of each upstream probe made by /status
+const STATUS_CHECK_TIMEOUT: u64 = 5; // seconds
 
 // Cache durations in seconds
 const BOOTSTRAP_CACHE_DURATION: u64 = 600; // 10 minutes
+const FIXTURES_CACHE_DURATION: u64 = 86_400; // 24 hours
+const FIXTURES_GAMEWEEK_CACHE_DURATION: u64 = 3_600; // 1 hour
+const LIVE_EVENT_CACHE_DURATION: u64 = 60; // 1 minute
+const PICKS_CACHE_DURATION: u64 = 600; // 10 minutes
+const DREAM_TEAM_CACHE_DURATION: u64 = 3_600; // 1 hour
+const H2H_STANDINGS_CACHE_DURATION: u64 = 300; // 5 minutes
+const H2H_MATCHES_CACHE_DURATION: u64 = 300; // 5 minutes
+const MANAGER_CUP_CACHE_DURATION: u64 = 600; // 10 minutes
+const LEAGUE_ALL_CACHE_DURATION: u64 = 300; // 5 minutes
+const CURRENT_GW_CACHE_DURATION: u64 = 600; // 10 minutes
+const PLAYER_HISTORY_CACHE_DURATION: u64 = 3_600; // 1 hour
+const TRANSFERS_CACHE_DURATION: u64 = 300; // 5 minutes
+const LEAGUE_TOP_CACHE_DURATION: u64 = 300; // 5 minutes
+const SEASON_HISTORY_CACHE_DURATION: u64 = 3_600; // 1 hour
+const LEAGUE_NEW_ENTRIES_CACHE_DURATION: u64 = 600; // 10 minutes
+const CHIP_HISTORY_CACHE_DURATION: u64 = 600; // 10 minutes
+const FIXTURES_UPCOMING_CACHE_DURATION: u64 = 300; // 5 minutes
+const GW_POINTS_CACHE_DURATION: u64 = 60; // 1 minute, as live points change during a gameweek
+const PLAYER_STATS_CACHE_DURATION: u64 = 300; // 5 minutes, as a row is added after each gameweek
+const LEAGUE_RANK_CACHE_DURATION: u64 = 300; // 5 minutes
+const PLAYER_FIXTURES_CACHE_DURATION: u64 = 3_600; // 1 hour
+const ELEMENT_FIXTURES_CACHE_DURATION: u64 = 3_600; // 1 hour
+const FORM_TREND_CACHE_DURATION: u64 = 300; // 5 minutes, as a row is added after each gameweek
+const LATEST_TRANSFERS_CACHE_DURATION: u64 = 300; // 5 minutes
+const PRICE_HISTORY_CACHE_DURATION: u64 = 3_600; // 1 hour
+const GW_BONUS_CACHE_DURATION: u64 = 60; // 1 minute, as bonus is confirmed during a gameweek
+const TOP_PERFORMERS_CACHE_DURATION: u64 = 60; // 1 minute, as live points change during a gameweek
+const DIFFERENTIALS_CACHE_DURATION: u64 = 60; // 1 minute, as live points change during a gameweek
+const CAPTAIN_CHOICES_CACHE_DURATION: u64 = 300; // 5 minutes
+const GW_RESULTS_CACHE_DURATION: u64 = 3_600; // 1 hour, as results are final once confirmed
+const SEASON_SUMMARY_CACHE_DURATION: u64 = 600; // 10 minutes
+const MINI_LEAGUES_CACHE_DURATION: u64 = 600; // 10 minutes
+const SIMULATED_H2H_CACHE_DURATION: u64 = 600; // 10 minutes
+
+// Gameweeks in a Premier League season
+const GAMEWEEK_COUNT: u32 = 38;
+
+// Premier League clubs, numbered 1 to 20 in bootstrap-static
+const TEAM_COUNT: u32 = 20;
+
+// Default and largest ?n= of /gameweek/:gw/top-performers
+const TOP_PERFORMERS_DEFAULT: usize = 10;
+const TOP_PERFORMERS_MAX: usize = 50;
+
+// Fixtures averaged by /player/:id/form-trend, and the longer window it is
+// compared against; averages further apart than the tolerance are a trend
+const FORM_RECENT_FIXTURES: usize = 5;
+const FORM_BASELINE_FIXTURES: usize = 10;
+const FORM_TREND_TOLERANCE: f64 = 0.5; // points
+
+// Players listed by /gameweek/:gw/captain-choices
+const CAPTAIN_CHOICES_COUNT: usize = 10;
+
+// Players listed in each direction by /gameweek/:gw/transfers-in-out
+const GW_TRANSFERS_COUNT: usize = 20;
+
+// Default ?threshold= of /gameweek/:gw/differentials, in percent of managers
+const DIFFERENTIAL_OWNERSHIP_DEFAULT: f64 = 10.0;
+
+// Largest N accepted by /league/:id/top/:n
+const LEAGUE_TOP_MAX: usize = 50;
+
+// Largest ?limit= accepted by the paginated fixtures endpoints
+const MAX_PAGE_LIMIT: usize = 50;
+
+// Query parameters interpreted by the proxy itself, never forwarded upstream
+// or made part of a cache key
+const PROXY_QUERY_PARAMS: &[&str] = &["limit", "offset", "fields", "callback", "pretty"];
+
+// Indentation of ?pretty JSON bodies
+const PRETTY_JSON_INDENT: &[u8] = b"    ";
+
+// Longest JSONP ?callback= name accepted
+const MAX_JSONP_CALLBACK_LEN: usize = 50;
+
+// Safety cap on the number of standings pages merged by /league/:id/standings/all
+const LEAGUE_ALL_MAX_PAGES: u32 = 200;
+
+// Entries per classic league standings page upstream
+const LEAGUE_STANDINGS_PAGE_SIZE: u64 = 50;
+
+// Pages /league/:league_id/rank/:manager_id checks on each side of the page
+// its search lands on, for ties across pages and entries lagging the standings
+const LEAGUE_RANK_SCAN_PAGES: u32 = 3;
+
+// Primary upstream retries: delays of 100 ms, 200 ms and 400 ms between
+// attempts, so back-off adds at most 700 ms (< 1 s) before falling back
+const PRIMARY_MAX_RETRIES: u32 = 3;
+const RETRY_BASE_DELAY_MS: u64 = 100;
+
+// Bodies smaller than this are not worth gzipping
+const MIN_COMPRESS_BYTES: usize = 1024;
+
+// Bulk element-summary: most IDs per request and upstream requests in flight
+const BULK_MAX_IDS: usize = 20;
+const BULK_CONCURRENCY: usize = 10;
+
+// Most players returned by /player/search
+const PLAYER_SEARCH_MAX: usize = 20;
+
+// Most players returned by /players
+const PLAYERS_MAX: usize = 100;
+
+// Ownership snapshots kept per player by /player/:id/ownership-trend
+const OWNERSHIP_HISTORY_MAX_POINTS: usize = 100;
+
+// Requests allowed per client IP per minute, overridable via RATE_LIMIT_RPM
+const DEFAULT_RATE_LIMIT_RPM: u32 = 60;
+const RATE_LIMIT_WINDOW: u64 = 60; // seconds
+
+// Circuit breaker settings for the primary FPL upstream
+const CIRCUIT_BREAKER_THRESHOLD: u32 = 5; // consecutive failures before opening
+const CIRCUIT_BREAKER_COOLDOWN: u64 = 30; // seconds to stay open
+
+// Largest request body accepted before routing
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+// Redis is an optimization; give up on it quickly and fall back to upstream
+const KV_TIMEOUT_MS: u64 = 500;
+
+/// Errors surfaced by the proxy, each mapped to an HTTP status code.
+#[derive(Debug)]
+pub(crate) enum ProxyError {
+    NotFound,
+    UnknownRoute,
+    UpstreamUnavailable,
+    InvalidPathParam(String),
+    JsonParseFailure(serde_json::Error),
+    MsgpackEncodeFailure(rmp_serde::encode::Error),
+    AllSourcesFailed,
+    RateLimited { retry_after_secs: u64 },
+    Unauthorized,
+    PayloadTooLarge,
+    EndpointDisabled,
+    NoBackupAvailable(&'static str),
+    ShuttingDown,
+    NotAcceptable,
+}
+
+impl ProxyError {
+    fn status_code(&self) -> u16 {
+        match self {
+            ProxyError::NotFound | ProxyError::UnknownRoute => 404,
+            ProxyError::InvalidPathParam(_) => 400,
+            ProxyError::UpstreamUnavailable => 503,
+            ProxyError::AllSourcesFailed => 502,
+            ProxyError::JsonParseFailure(_) => 500,
+            ProxyError::MsgpackEncodeFailure(_) => 500,
+            ProxyError::RateLimited { .. } => 429,
+            ProxyError::Unauthorized => 401,
+            ProxyError::PayloadTooLarge => 413,
+            ProxyError::EndpointDisabled => 503,
+            ProxyError::NoBackupAvailable(_) => 503,
+            ProxyError::ShuttingDown => 503,
+            ProxyError::NotAcceptable => 406,
+        }
+    }
+}
+
+impl fmt::Display for ProxyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProxyError::NotFound | ProxyError::UnknownRoute => write!(f, "Not Found"),
+            ProxyError::UpstreamUnavailable => write!(f, "Upstream API is unavailable"),
+            ProxyError::InvalidPathParam(param) => write!(f, "Invalid {}", param),
+            ProxyError::JsonParseFailure(e) => write!(f, "JSON error: {}", e),
+            ProxyError::MsgpackEncodeFailure(e) => write!(f, "MessagePack error: {}", e),
+            ProxyError::AllSourcesFailed => write!(f, "Failed to fetch data from all available sources"),
+            ProxyError::RateLimited { .. } => write!(f, "Too Many Requests"),
+            ProxyError::Unauthorized => write!(f, "Unauthorized"),
+            ProxyError::PayloadTooLarge => write!(f, "Request body exceeds {} bytes", MAX_BODY_BYTES),
+            ProxyError::EndpointDisabled => write!(f, "endpoint_disabled"),
+            ProxyError::NoBackupAvailable(endpoint) => {
+                write!(f, "The FPL API is unavailable and {} has no backup data; try again later", endpoint)
+            }
+            ProxyError::ShuttingDown => write!(f, "Server is shutting down"),
+            ProxyError::NotAcceptable => {
+                write!(f, "Not Acceptable: responses are application/json, text/json or application/msgpack")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProxyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProxyError::JsonParseFailure(e) => Some(e),
+            ProxyError::MsgpackEncodeFailure(e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 // Global state using OnceLock for initialization
 static HTTP_CLIENT: OnceLock<Client> = OnceLock::new();
-static CACHE: OnceLock<Cache<String, Value>> = OnceLock::new();
+static UPSTREAM_TIMEOUT: OnceLock<Duration> = OnceLock::new();
+static CACHE: OnceLock<Cache<String, CachedValue>> = OnceLock::new();
+static CIRCUIT_BREAKER: OnceLock<CircuitBreaker> = OnceLock::new();
+static FPL_API_BASE: OnceLock<String> = OnceLock::new();
+static BACKUP_API_BASE: OnceLock<String> = OnceLock::new();
+static RATE_LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+static ALLOWED_ORIGINS: OnceLock<Option<Vec<String>>> = OnceLock::new();
+static FEATURE_FLAGS: OnceLock<FeatureFlags> = OnceLock::new();
+static ROUTER: OnceLock<Router> = OnceLock::new();
+static TRACING: OnceLock<()> = OnceLock::new();
+static DEBUG_MODE: OnceLock<bool> = OnceLock::new();
+static OWNERSHIP_HISTORY: OnceLock<Cache<u32, OwnershipHistory>> = OnceLock::new();
+static KV_CACHE: OnceCell<Option<KvCache>> = OnceCell::const_new();
+static SHUTDOWN: Shutdown = Shutdown::new(MAX_IN_FLIGHT_REQUESTS);
+tokio::task_local! {
+    // Set for requests sent with Cache-Control: no-cache or Pragma: no-cache
+    static CACHE_BYPASS: bool;
+}
 
-fn get_http_client() -> &'static Client {
-    HTTP_CLIENT.get_or_init(|| {
-        Client::builder()
-            .timeout(Duration::from_secs(30))
-            .user_agent("Fantasy-PL-Proxy-Rust/1.0")
-            .build()
-            .expect("Failed to create HTTP client")
-    })
+static CACHE_HITS: AtomicU64 = AtomicU64::new(0);
+static CACHE_MISSES: AtomicU64 = AtomicU64::new(0);
+
+/// Endpoint toggles read from `ENABLE_*` environment variables; every flag defaults to on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+struct FeatureFlags {
+    bulk_endpoint: bool,
+    h2h_endpoint: bool,
+    league_all_endpoint: bool,
 }
 
-fn get_cache() -> &'static Cache<String, Value> {
-    CACHE.get_or_init(|| {
-        Cache::builder()
-            .max_capacity(1000)
-            .time_to_live(Duration::from_secs(BOOTSTRAP_CACHE_DURATION))
-            .build()
-    })
+impl FeatureFlags {
+    fn from_env() -> Self {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        // Anything but an explicit "off" value leaves the flag enabled
+        let enabled = |name: &str| {
+            lookup(name).is_none_or(|value| {
+                !matches!(value.trim().to_ascii_lowercase().as_str(), "0" | "false" | "off" | "no")
+            })
+        };
+
+        Self {
+            bulk_endpoint: enabled("ENABLE_BULK_ENDPOINT"),
+            h2h_endpoint: enabled("ENABLE_H2H_ENDPOINT"),
+            league_all_endpoint: enabled("ENABLE_LEAGUE_ALL_ENDPOINT"),
+        }
+    }
 }
 
-// Load backup JSON data from embedded files
-fn load_backup_data(endpoint: &str) -> Option<Value> {
-    match endpoint {
-        "bootstrap-static" => {
-            let backup_json = include_str!("../backup-data/bootstrap-static.json");
-            serde_json::from_str(backup_json).ok()
-        },
-        "fixtures" => {
-            let backup_json = include_str!("../backup-data/fixtures.json");
-            serde_json::from_str(backup_json).ok()
-        },
-        "live-event" => {
-            let backup_json = include_str!("../backup-data/live-event.json");
-            serde_json::from_str(backup_json).ok()
-        },
-        _ => None
+/// Connection pool limits of the upstream HTTP client; `None` keeps reqwest's default
+/// (no idle-connection limit and a 90 s idle timeout).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PoolSettings {
+    max_idle_per_host: Option<usize>,
+    idle_timeout: Option<Duration>,
+}
+
+impl PoolSettings {
+    fn from_env() -> Self {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    // Unparseable values are ignored like unset ones, as with FPL_TIMEOUT_SECS
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        Self {
+            max_idle_per_host: lookup("FPL_MAX_CONNECTIONS").and_then(|value| value.trim().parse().ok()),
+            idle_timeout: lookup("FPL_CONNECTION_IDLE_TIMEOUT_SECS")
+                .and_then(|value| value.trim().parse().ok())
+                .map(Duration::from_secs),
+        }
     }
 }
 
-async fn fetch_with_fallback(primary_url: &str, backup_url: Option<&str>, local_backup: Option<&str>) -> Result<Value, String> {
-    let client = get_http_client();
-    let mut is_503_error = false;
+/// Fixed-window request counter per client IP.
+struct RateLimiter {
+    counters: Cache<String, Arc<AtomicU32>>,
+    limit: u32,
+}
 
-    // Try primary URL first
-    match client.get(primary_url).send().await {
-        Ok(response) => {
-            let status = response.status();
-            if status.is_success() {
-                match response.json::<Value>().await {
-                    Ok(data) => return Ok(data),
-                    Err(e) => error!("Failed to parse JSON from primary URL {}: {}", primary_url, e),
-                }
-            } else {
-                if status.as_u16() == 503 {
-                    is_503_error = true;
-                    warn!("Received 503 Service Unavailable from primary URL: {}", primary_url);
-                } else {
-                    error!("Received non-success status {} from primary URL {}", status, primary_url);
-                }
-            }
+impl RateLimiter {
+    fn from_env() -> Self {
+        let limit = std::env::var("RATE_LIMIT_RPM")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_RATE_LIMIT_RPM);
+
+        Self {
+            counters: Cache::builder()
+                .max_capacity(10_000)
+                .time_to_live(Duration::from_secs(RATE_LIMIT_WINDOW))
+                .build(),
+            limit,
         }
-        Err(e) => {
-            error!("Failed to fetch from primary URL {}: {}", primary_url, e);
-            // Network errors might indicate overload, treat as potential 503
-            is_503_error = true;
+    }
+
+    async fn check(&self, client_ip: &str) -> Result<(), ProxyError> {
+        let counter = self
+            .counters
+            .get_with(client_ip.to_string(), async { Arc::new(AtomicU32::new(0)) })
+            .await;
+
+        if counter.fetch_add(1, Ordering::Relaxed) >= self.limit {
+            warn!("Rate limit exceeded for client {}", client_ip);
+            return Err(ProxyError::RateLimited {
+                retry_after_secs: RATE_LIMIT_WINDOW,
+            });
         }
+
+        Ok(())
     }
+}
 
-    // Try backup URL if available
-    if let Some(backup_url) = backup_url {
-        match client.get(backup_url).send().await {
-            Ok(response) => {
-                let status = response.status();
-                if status.is_success() {
-                    match response.json::<Value>().await {
-                        Ok(data) => return Ok(data),
-                        Err(e) => error!("Failed to parse JSON from backup URL {}: {}", backup_url, e),
-                    }
-                } else {
-                    if status.as_u16() == 503 {
-                        warn!("Received 503 Service Unavailable from backup URL: {}", backup_url);
-                    } else {
-                        error!("Received non-success status {} from backup URL {}", status, backup_url);
-                    }
-                }
-            }
-            Err(e) => error!("Failed to fetch from backup URL {}: {}", backup_url, e),
+/// Skips the primary upstream after repeated failures until a cool-down has passed.
+struct CircuitBreaker {
+    consecutive_failures: AtomicU32,
+    last_failure_ms: AtomicU64,
+    threshold: u32,
+    cooldown: Duration,
+}
+
+impl CircuitBreaker {
+    fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            consecutive_failures: AtomicU32::new(0),
+            last_failure_ms: AtomicU64::new(0),
+            threshold,
+            cooldown,
         }
     }
 
-    // If we encountered 503 errors or network issues, try local backup data
-    if is_503_error {
-        if let Some(backup_endpoint) = local_backup {
-            if let Some(backup_data) = load_backup_data(backup_endpoint) {
-                warn!("Using local backup data for endpoint: {}", backup_endpoint);
-                return Ok(backup_data);
-            }
+    // Open while the threshold is reached and the cool-down has not elapsed.
+    // Once it elapses a single caller is let through to probe the upstream: it
+    // restarts the cool-down, so concurrent callers stay open until the probe
+    // records a success, or a failure that keeps the breaker open.
+    fn is_open(&self) -> bool {
+        if self.consecutive_failures.load(Ordering::Relaxed) < self.threshold {
+            return false;
+        }
+        let last_failure_ms = self.last_failure_ms.load(Ordering::Relaxed);
+        let now_ms = now_millis();
+        if now_ms.saturating_sub(last_failure_ms) < self.cooldown.as_millis() as u64 {
+            return true;
+        }
+
+        // Only the caller that moves last_failure_ms forward gets the probe
+        let probe = self
+            .last_failure_ms
+            .compare_exchange(last_failure_ms, now_ms, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok();
+        !probe
+    }
+
+    fn record_success(&self) {
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+    }
+
+    fn record_failure(&self) {
+        self.last_failure_ms.store(now_millis(), Ordering::Relaxed);
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures == self.threshold {
+            warn!("Circuit breaker opened after {} consecutive primary failures", failures);
         }
     }
+}
 
-    Err("Failed to fetch data from all available sources".to_string())
+/// Tracks in-flight work so Ctrl-C can let it finish, KV cache writes included.
+struct Shutdown {
+    requested: AtomicBool,
+    in_flight: Semaphore,
+    capacity: u32,
 }
 
-async fn get_cached_or_fetch(cache_key: &str, primary_url: &str, backup_url: Option<&str>, local_backup: Option<&str>) -> Result<Value, String> {
-    let cache = get_cache();
+impl Shutdown {
+    const fn new(capacity: u32) -> Self {
+        Self {
+            requested: AtomicBool::new(false),
+            in_flight: Semaphore::const_new(capacity as usize),
+            capacity,
+        }
+    }
+
+    // A permit held for as long as the work runs, or None once shutdown has begun
+    async fn begin(&self) -> Option<SemaphorePermit<'_>> {
+        if self.requested.load(Ordering::Acquire) {
+            return None;
+        }
+        self.in_flight.acquire().await.ok()
+    }
+
+    // Refuse new work, then wait up to `grace` for the running work to finish.
+    // Returns how many tasks were still running when the grace period ran out.
+    async fn drain(&self, grace: Duration) -> u32 {
+        self.requested.store(true, Ordering::Release);
+        let drained = tokio::time::timeout(grace, self.in_flight.acquire_many(self.capacity)).await;
+        let remaining = match drained {
+            Ok(_) => 0,
+            Err(_) => self.capacity - self.in_flight.available_permits() as u32,
+        };
+        self.in_flight.close();
+        remaining
+    }
+}
 
-    // Check cache first
-    if let Some(cached_data) = cache.get(cache_key).await {
-        return Ok(cached_data);
+// Resolves once Ctrl-C has been handled and in-flight requests have finished
+async fn shutdown_on_ctrl_c() {
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        warn!("Cannot listen for Ctrl-C, graceful shutdown disabled: {}", e);
+        return std::future::pending().await;
     }
 
-    // Fetch from API with all fallback mechanisms
-    let data = fetch_with_fallback(primary_url, backup_url, local_backup).await?;
+    info!("Shutdown requested, waiting for in-flight requests");
+    match SHUTDOWN.drain(Duration::from_secs(SHUTDOWN_GRACE_PERIOD)).await {
+        0 => info!("All requests finished, exiting cleanly"),
+        remaining => warn!(
+            "{} requests still running after {} s, exiting anyway",
+            remaining, SHUTDOWN_GRACE_PERIOD
+        ),
+    }
+}
 
-    // Cache the result
-    cache.insert(cache_key.to_string(), data.clone()).await;
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
 
-    Ok(data)
+/// TTL applied to every cache key built from `prefix`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CachePolicy {
+    prefix: &'static str,
+    ttl: Duration,
 }
 
-fn extract_path_param(uri: &str, pattern: &str, param_name: &str) -> Option<String> {
-    // Simple path parameter extraction
-    // For more complex routing, you might want to use a proper router library
-    let pattern_parts: Vec<&str> = pattern.split('/').collect();
-    let uri_parts: Vec<&str> = uri.split('/').collect();
+impl CachePolicy {
+    const BOOTSTRAP_STATIC: CachePolicy = CachePolicy::new("bootstrap-static", BOOTSTRAP_CACHE_DURATION);
+    const TEAMS: CachePolicy = CachePolicy::new("teams", BOOTSTRAP_CACHE_DURATION);
+    const EVENTS: CachePolicy = CachePolicy::new("events", BOOTSTRAP_CACHE_DURATION);
+    const ELEMENT_TYPES: CachePolicy = CachePolicy::new("element-types", BOOTSTRAP_CACHE_DURATION);
+    const PLAYERS: CachePolicy = CachePolicy::new("players", BOOTSTRAP_CACHE_DURATION);
+    const TEAM_PLAYERS: CachePolicy = CachePolicy::new("team-players", BOOTSTRAP_CACHE_DURATION);
+    const GW_TRANSFERS: CachePolicy = CachePolicy::new("gw-transfers", BOOTSTRAP_CACHE_DURATION);
+    const FIXTURES: CachePolicy = CachePolicy::new("fixtures", FIXTURES_CACHE_DURATION);
+    const FIXTURES_GAMEWEEK: CachePolicy = CachePolicy::new("fixtures-gw", FIXTURES_GAMEWEEK_CACHE_DURATION);
+    const LIVE_EVENT: CachePolicy = CachePolicy::new("live-event", LIVE_EVENT_CACHE_DURATION);
+    const PICKS: CachePolicy = CachePolicy::new("picks", PICKS_CACHE_DURATION);
+    const DREAM_TEAM: CachePolicy = CachePolicy::new("dream-team", DREAM_TEAM_CACHE_DURATION);
+    const H2H_STANDINGS: CachePolicy = CachePolicy::new("h2h-standings", H2H_STANDINGS_CACHE_DURATION);
+    const H2H_MATCHES: CachePolicy = CachePolicy::new("h2h-matches", H2H_MATCHES_CACHE_DURATION);
+    const MANAGER_CUP: CachePolicy = CachePolicy::new("cup", MANAGER_CUP_CACHE_DURATION);
+    const LEAGUE_ALL: CachePolicy = CachePolicy::new("league-all", LEAGUE_ALL_CACHE_DURATION);
+    const CURRENT_GW: CachePolicy = CachePolicy::new("current-gw", CURRENT_GW_CACHE_DURATION);
+    const PLAYER_HISTORY: CachePolicy = CachePolicy::new("player-history", PLAYER_HISTORY_CACHE_DURATION);
+    const TRANSFERS: CachePolicy = CachePolicy::new("transfers", TRANSFERS_CACHE_DURATION);
+    const LEAGUE_TOP: CachePolicy = CachePolicy::new("league-top", LEAGUE_TOP_CACHE_DURATION);
+    const SEASON_HISTORY: CachePolicy = CachePolicy::new("season-history", SEASON_HISTORY_CACHE_DURATION);
+    const LEAGUE_NEW_ENTRIES: CachePolicy = CachePolicy::new("league-new-entries", LEAGUE_NEW_ENTRIES_CACHE_DURATION);
+    const CHIP_HISTORY: CachePolicy = CachePolicy::new("chips", CHIP_HISTORY_CACHE_DURATION);
+    const FIXTURES_UPCOMING: CachePolicy = CachePolicy::new("fixtures-upcoming", FIXTURES_UPCOMING_CACHE_DURATION);
+    const GW_POINTS: CachePolicy = CachePolicy::new("points", GW_POINTS_CACHE_DURATION);
+    const PLAYER_STATS: CachePolicy = CachePolicy::new("player-stats", PLAYER_STATS_CACHE_DURATION);
+    const LEAGUE_RANK: CachePolicy = CachePolicy::new("rank", LEAGUE_RANK_CACHE_DURATION);
+    const GW_BONUS: CachePolicy = CachePolicy::new("bonus", GW_BONUS_CACHE_DURATION);
+    const TOP_PERFORMERS: CachePolicy = CachePolicy::new("top-performers", TOP_PERFORMERS_CACHE_DURATION);
+    const DIFFERENTIALS: CachePolicy = CachePolicy::new("differentials", DIFFERENTIALS_CACHE_DURATION);
+    const CAPTAIN_CHOICES: CachePolicy = CachePolicy::new("captain-choices", CAPTAIN_CHOICES_CACHE_DURATION);
+    const GW_RESULTS: CachePolicy = CachePolicy::new("gw-results", GW_RESULTS_CACHE_DURATION);
+    const SEASON_SUMMARY: CachePolicy = CachePolicy::new("season-summary", SEASON_SUMMARY_CACHE_DURATION);
+    const MINI_LEAGUES: CachePolicy = CachePolicy::new("mini-leagues", MINI_LEAGUES_CACHE_DURATION);
+    const SIMULATED_H2H: CachePolicy = CachePolicy::new("simulated-h2h", SIMULATED_H2H_CACHE_DURATION);
+    const PLAYER_FIXTURES: CachePolicy = CachePolicy::new("upcoming-fixtures-player", PLAYER_FIXTURES_CACHE_DURATION);
+    const ELEMENT_FIXTURES: CachePolicy = CachePolicy::new("player-fixtures", ELEMENT_FIXTURES_CACHE_DURATION);
+    const FORM_TREND: CachePolicy = CachePolicy::new("form-trend", FORM_TREND_CACHE_DURATION);
+    const LATEST_TRANSFERS: CachePolicy = CachePolicy::new("latest-transfers", LATEST_TRANSFERS_CACHE_DURATION);
+    const PRICE_HISTORY: CachePolicy = CachePolicy::new("price-history", PRICE_HISTORY_CACHE_DURATION);
 
-    if pattern_parts.len() != uri_parts.len() {
-        return None;
+    // Every policy above; the allowlist of key prefixes DELETE /cache/:key accepts
+    const ALL: &'static [CachePolicy] = &[
+        CachePolicy::BOOTSTRAP_STATIC,
+        CachePolicy::TEAMS,
+        CachePolicy::EVENTS,
+        CachePolicy::ELEMENT_TYPES,
+        CachePolicy::PLAYERS,
+        CachePolicy::TEAM_PLAYERS,
+        CachePolicy::GW_TRANSFERS,
+        CachePolicy::FIXTURES,
+        CachePolicy::FIXTURES_GAMEWEEK,
+        CachePolicy::LIVE_EVENT,
+        CachePolicy::PICKS,
+        CachePolicy::DREAM_TEAM,
+        CachePolicy::H2H_STANDINGS,
+        CachePolicy::H2H_MATCHES,
+        CachePolicy::MANAGER_CUP,
+        CachePolicy::LEAGUE_ALL,
+        CachePolicy::CURRENT_GW,
+        CachePolicy::PLAYER_HISTORY,
+        CachePolicy::TRANSFERS,
+        CachePolicy::LEAGUE_TOP,
+        CachePolicy::SEASON_HISTORY,
+        CachePolicy::LEAGUE_NEW_ENTRIES,
+        CachePolicy::CHIP_HISTORY,
+        CachePolicy::FIXTURES_UPCOMING,
+        CachePolicy::GW_POINTS,
+        CachePolicy::PLAYER_STATS,
+        CachePolicy::LEAGUE_RANK,
+        CachePolicy::GW_BONUS,
+        CachePolicy::TOP_PERFORMERS,
+        CachePolicy::DIFFERENTIALS,
+        CachePolicy::CAPTAIN_CHOICES,
+        CachePolicy::GW_RESULTS,
+        CachePolicy::SEASON_SUMMARY,
+        CachePolicy::MINI_LEAGUES,
+        CachePolicy::SIMULATED_H2H,
+        CachePolicy::PLAYER_FIXTURES,
+        CachePolicy::ELEMENT_FIXTURES,
+        CachePolicy::FORM_TREND,
+        CachePolicy::LATEST_TRANSFERS,
+        CachePolicy::PRICE_HISTORY,
+    ];
+
+    pub(crate) const fn new(prefix: &'static str, ttl_secs: u64) -> Self {
+        Self {
+            prefix,
+            ttl: Duration::from_secs(ttl_secs),
+        }
+    }
+
+    /// Builds the cache key for this policy, e.g. `live-event-12`.
+    fn cache_key(&self, params: &[&(dyn fmt::Display + Sync)]) -> String {
+        let mut key = self.prefix.to_string();
+        for param in params {
+            key.push_str(&format!("-{}", param));
+        }
+        key
     }
+}
+
+// Cached data together with its ETag and the TTL it was inserted with
+#[derive(Clone)]
+struct CachedValue {
+    data: CachedData,
+    etag: String,
+    last_modified: Option<String>,
+    source: DataSource,
+    ttl: Duration,
+}
 
-    for (i, pattern_part) in pattern_parts.iter().enumerate() {
-        if pattern_part.starts_with(':') && &pattern_part[1..] == param_name {
-            return Some(uri_parts[i].to_string());
+// Payload of a cache entry; bootstrap-static is stored typed so schema drift
+// is caught when it is fetched rather than when a derived endpoint reads it
+#[derive(Clone)]
+enum CachedData {
+    Json(Value),
+    Bootstrap(Arc<BootstrapStatic>),
+}
+
+impl CachedData {
+    fn to_json(&self) -> Result<Value, ProxyError> {
+        match self {
+            CachedData::Json(data) => Ok(data.clone()),
+            CachedData::Bootstrap(bootstrap) => {
+                serde_json::to_value(bootstrap.as_ref()).map_err(ProxyError::JsonParseFailure)
+            }
         }
     }
+}
 
-    None
+/// Whether a response was served from the cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CacheStatus {
+    Hit,
+    Miss,
+    Bypass,
 }
 
-async fn handle_bootstrap_static() -> Result<Value, String> {
-    let primary_url = format!("{}/bootstrap-static/", FPL_API_BASE);
-    let backup_url = format!("{}/{}/bootstrap-static.json", BACKUP_API_BASE, BACKUP_SEASON);
+impl CacheStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CacheStatus::Hit => "HIT",
+            CacheStatus::Miss => "MISS",
+            CacheStatus::Bypass => "BYPASS",
+        }
+    }
+}
+
+/// Which of the fallback sources produced a response, from least to most
+/// degraded, so the `max` of several sources is the worst one involved.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum DataSource {
+    #[default]
+    Primary,
+    BackupUrl,
+    LocalBackup,
+}
 
-    get_cached_or_fetch("bootstrap-static", &primary_url, Some(&backup_url), Some("bootstrap-static")).await
+/// Upstream JSON together with the upstream `Last-Modified` header, if any,
+/// and the source it came from.
+struct FetchResult {
+    data: Value,
+    last_modified: Option<String>,
+    source: DataSource,
 }
 
-async fn handle_fixtures() -> Result<Value, String> {
-    let primary_url = format!("{}/fixtures/", FPL_API_BASE);
-    let backup_url = format!("{}/{}/fixtures.json", BACKUP_API_BASE, BACKUP_SEASON);
+/// Successful handler output: the JSON payload, its validators and how it was served.
+pub(crate) struct Payload {
+    data: Value,
+    etag: String,
+    last_modified: Option<String>,
+    cache_status: CacheStatus,
+    source: DataSource,
+    // Extra response headers set by the handler, e.g. pagination links
+    headers: Vec<(&'static str, String)>,
+}
 
-    fetch_with_fallback(&primary_url, Some(&backup_url), Some("fixtures")).await
+impl Payload {
+    fn from_cache(cached: CachedValue) -> Result<Self, ProxyError> {
+        Ok(Self {
+            data: cached.data.to_json()?,
+            etag: cached.etag,
+            last_modified: cached.last_modified,
+            cache_status: CacheStatus::Hit,
+            source: cached.source,
+            headers: Vec::new(),
+        })
+    }
 }
 
-async fn handle_element_summary(id: &str) -> Result<Value, String> {
-    let url = format!("{}/element-summary/{}/", FPL_API_BASE, id);
-    fetch_with_fallback(&url, None, None).await
+// Uncached payloads are always a cache bypass, computed by the proxy itself
+impl From<Value> for Payload {
+    fn from(data: Value) -> Self {
+        let etag = compute_etag(&data);
+        Self {
+            data,
+            etag,
+            last_modified: None,
+            cache_status: CacheStatus::Bypass,
+            source: DataSource::Primary,
+            headers: Vec::new(),
+        }
+    }
 }
 
-async fn handle_live_event(gw: &str) -> Result<Value, String> {
-    let url = format!("{}/event/{}/live/", FPL_API_BASE, gw);
-    let cache_key = format!("live-event-{}", gw);
+impl From<FetchResult> for Payload {
+    fn from(fetched: FetchResult) -> Self {
+        Self {
+            last_modified: fetched.last_modified,
+            source: fetched.source,
+            ..Payload::from(fetched.data)
+        }
+    }
+}
 
-    get_cached_or_fetch(&cache_key, &url, None, Some("live-event")).await
+// Strong ETag derived from the SHA-256 of the serialized JSON
+pub(crate) fn compute_etag(data: &Value) -> String {
+    let digest = Sha256::digest(data.to_string().as_bytes());
+    let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("\"{}\"", hex)
 }
 
-async fn handle_picks(manager_id: &str, gw: &str) -> Result<Value, String> {
-    let url = format!("{}/entry/{}/event/{}/picks/", FPL_API_BASE, manager_id, gw);
-    let cache_key = format!("picks-{}-{}", manager_id, gw);
+// Whether an If-None-Match header value matches the current ETag
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match
+        .split(',')
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == "*" || tag == etag)
+}
 
-    get_cached_or_fetch(&cache_key, &url, None, None).await
+// Whether the resource is unchanged since an If-Modified-Since date; unparseable dates never match
+fn not_modified_since(if_modified_since: &str, last_modified: &str) -> bool {
+    match (
+        chrono::DateTime::parse_from_rfc2822(if_modified_since.trim()),
+        chrono::DateTime::parse_from_rfc2822(last_modified.trim()),
+    ) {
+        (Ok(since), Ok(modified)) => modified <= since,
+        _ => false,
+    }
 }
 
-async fn handle_manager_info(id: &str) -> Result<Value, String> {
-    let url = format!("{}/entry/{}/", FPL_API_BASE, id);
-    fetch_with_fallback(&url, None, None).await
+// Conditional GET check; If-None-Match takes precedence over If-Modified-Since
+fn is_not_modified(
+    if_none_match: Option<&str>,
+    if_modified_since: Option<&str>,
+    etag: &str,
+    last_modified: Option<&str>,
+) -> bool {
+    match (if_none_match, if_modified_since, last_modified) {
+        (Some(tags), _, _) => etag_matches(tags, etag),
+        (None, Some(since), Some(modified)) => not_modified_since(since, modified),
+        _ => false,
+    }
 }
 
-async fn handle_manager_transfers(id: &str) -> Result<Value, String> {
-    let url = format!("{}/entry/{}/transfers/", FPL_API_BASE, id);
-    fetch_with_fallback(&url, None, None).await
+fn last_modified_header(response: &reqwest::Response) -> Option<String> {
+    response
+        .headers()
+        .get("last-modified")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
 }
 
-async fn handle_manager_history(id: &str) -> Result<Value, String> {
-    let url = format!("{}/entry/{}/history/", FPL_API_BASE, id);
-    fetch_with_fallback(&url, None, None).await
+// Per-entry expiry so each cache key honours its own CachePolicy
+struct PolicyExpiry;
+
+impl Expiry<String, CachedValue> for PolicyExpiry {
+    fn expire_after_create(&self, _key: &String, value: &CachedValue, _created_at: Instant) -> Option<Duration> {
+        Some(value.ttl)
+    }
+
+    fn expire_after_update(
+        &self,
+        _key: &String,
+        value: &CachedValue,
+        _updated_at: Instant,
+        _duration_until_expiry: Option<Duration>,
+    ) -> Option<Duration> {
+        Some(value.ttl)
+    }
 }
 
-async fn handle_league_standings(league_id: &str, page: &str) -> Result<Value, String> {
-    let url = format!("{}/leagues-classic/{}/standings/?page_standings={}", FPL_API_BASE, league_id, page);
-    fetch_with_fallback(&url, None, None).await
+fn fpl_api_base() -> &'static str {
+    FPL_API_BASE.get_or_init(|| std::env::var("FPL_API_BASE").unwrap_or_else(|_| DEFAULT_FPL_API_BASE.to_string()))
 }
 
-async fn handle_league_standings_by_phase(league_id: &str, phase: &str) -> Result<Value, String> {
-    let url = format!("{}/leagues-classic/{}/standings/?page_standings=1&phase={}", FPL_API_BASE, league_id, phase);
-    fetch_with_fallback(&url, None, None).await
+fn backup_api_base() -> &'static str {
+    BACKUP_API_BASE.get_or_init(|| std::env::var("BACKUP_API_BASE").unwrap_or_else(|_| DEFAULT_BACKUP_API_BASE.to_string()))
 }
 
-async fn handler(request: Request) -> Result<Response<Body>, Error> {
-    // Initialize tracing if not already done
-    tracing_subscriber::fmt()
-        .with_env_filter("info")
-        .try_init()
-        .ok();
+// Total time budget for one upstream fetch, shared by primary and backup URLs
+fn upstream_timeout() -> Duration {
+    *UPSTREAM_TIMEOUT.get_or_init(|| {
+        let secs = std::env::var("FPL_TIMEOUT_SECS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_UPSTREAM_TIMEOUT);
+        Duration::from_secs(secs)
+    })
+}
 
-    let path = request.uri().path();
-    info!("Handling request to: {}", path);
+fn get_http_client() -> &'static Client {
+    HTTP_CLIENT.get_or_init(|| {
+        let mut builder = Client::builder()
+            .timeout(upstream_timeout())
+            .user_agent("Fantasy-PL-Proxy-Rust/1.0");
 
-    // Route matching and handling
-    let result = match path {
-        "/health" => {
-            Ok(json!({
-                "status": "OK",
-                "service": "Fantasy PL Vercel Proxy (Rust)",
-                "timestamp": chrono::Utc::now().to_rfc3339()
-            }))
-        }
-        "/bootstrap-static" => handle_bootstrap_static().await,
-        "/fixtures" => handle_fixtures().await,
-        path if path.starts_with("/element-summary/") => {
-            if let Some(id) = extract_path_param(path, "/element-summary/:id", "id") {
-                handle_element_summary(&id).await
-            } else {
-                Err("Invalid element ID".to_string())
-            }
-        }
-        path if path.starts_with("/live-event/") => {
-            if let Some(gw) = extract_path_param(path, "/live-event/:gw", "gw") {
-                handle_live_event(&gw).await
-            } else {
-                Err("Invalid gameweek".to_string())
-            }
-        }
-        path if path.starts_with("/picks/") => {
-            // Handle /picks/:manager_id/:gw
-            let parts: Vec<&str> = path.split('/').collect();
-            if parts.len() == 4 && parts[1] == "picks" {
-                handle_picks(parts[2], parts[3]).await
-            } else {
-                Err("Invalid picks path".to_string())
-            }
-        }
-        path if path.starts_with("/manager/") => {
-            let parts: Vec<&str> = path.split('/').collect();
-            if parts.len() >= 3 {
-                let manager_id = parts[2];
-                if parts.len() == 3 {
-                    // /manager/:id
-                    handle_manager_info(manager_id).await
-                } else if parts.len() == 4 {
-                    match parts[3] {
-                        "transfers" => handle_manager_transfers(manager_id).await,
-                        "history" => handle_manager_history(manager_id).await,
-                        _ => Err("Invalid manager endpoint".to_string()),
-                    }
-                } else {
-                    Err("Invalid manager path".to_string())
-                }
-            } else {
-                Err("Invalid manager path".to_string())
-            }
+        let pool = PoolSettings::from_env();
+        if let Some(max_idle) = pool.max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max_idle);
         }
-        path if path.starts_with("/league/") => {
-            let parts: Vec<&str> = path.split('/').collect();
-            if parts.len() == 4 && parts[1] == "league" {
-                // /league/:league_id/:page
-                handle_league_standings(parts[2], parts[3]).await
-            } else if parts.len() == 5 && parts[1] == "league" && parts[2] == "mon" {
-                // /league/mon/:league_id/:phase
-                handle_league_standings_by_phase(parts[3], parts[4]).await
-            } else {
-                Err("Invalid league path".to_string())
-            }
+        if let Some(idle_timeout) = pool.idle_timeout {
+            builder = builder.pool_idle_timeout(idle_timeout);
         }
-        _ => Err("Not Found".to_string()),
-    };
-
-    // Convert result to Response
-    match result {
-        Ok(data) => {
-            let json_body = serde_json::to_string(&data).map_err(|e| {
-                error!("Failed to serialize JSON: {}", e);
-                Error::from("JSON serialization error")
-            })?;
 
-            Response::builder()
-                .status(200)
-                .header("content-type", "application/json")
-                .header("access-control-allow-origin", "*")
-                .header("access-control-allow-methods", "GET, POST, PUT, DELETE, OPTIONS")
-                .header("access-control-allow-headers", "Content-Type, Authorization")
-                .header("cache-control", "public, max-age=300") // 5 minutes cache
-                .body(Body::from(json_body))
-                .map_err(Error::from)
+        // Fail closed: a malformed pin must not silently disable pinning
+        if let Ok(pins) = std::env::var("PINNED_FPL_CERT_SHA256") {
+            let fingerprints = tls::parse_fingerprints(&pins)
+                .unwrap_or_else(|e| panic!("Invalid PINNED_FPL_CERT_SHA256: {}", e));
+            let host = reqwest::Url::parse(fpl_api_base())
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_string))
+                .expect("FPL_API_BASE must be an absolute URL to pin its certificate");
+            info!("Pinning the TLS certificate of {} to {} fingerprint(s)", host, fingerprints.len());
+            builder = builder.use_preconfigured_tls(tls::pinned_client_config(&host, fingerprints));
         }
-        Err(error_msg) => {
-            error!("Request error: {}", error_msg);
 
-            let error_json = json!({
-                "error": error_msg,
-                "timestamp": chrono::Utc::now().to_rfc3339()
-            });
+        builder.build().expect("Failed to create HTTP client")
+    })
+}
+
+fn get_circuit_breaker() -> &'static CircuitBreaker {
+    CIRCUIT_BREAKER.get_or_init(|| {
+        CircuitBreaker::new(CIRCUIT_BREAKER_THRESHOLD, Duration::from_secs(CIRCUIT_BREAKER_COOLDOWN))
+    })
+}
 
-            let status = if error_msg == "Not Found" { 404 } else { 500 };
+fn feature_flags() -> &'static FeatureFlags {
+    FEATURE_FLAGS.get_or_init(FeatureFlags::from_env)
+}
 
-            Response::builder()
-                .status(status)
-                .header("content-type", "application/json")
-                .header("access-control-allow-origin", "*")
-                .header("access-control-allow-methods", "GET, POST, PUT, DELETE, OPTIONS")
-                .header("access-control-allow-headers", "Content-Type, Authorization")
-                .body(Body::from(error_json.to_string()))
-                .map_err(Error::from)
+// Guard for routes behind a feature flag
+fn require_enabled(enabled: bool) -> Result<(), ProxyError> {
+    if enabled {
+        Ok(())
+    } else {
+        Err(ProxyError::EndpointDisabled)
+    }
+}
+
+// DEBUG_MODE enables development-only behaviour such as ?pretty; unlike the
+// feature flags it is off unless explicitly switched on
+fn debug_mode() -> bool {
+    *DEBUG_MODE.get_or_init(|| {
+        std::env::var("DEBUG_MODE")
+            .is_ok_and(|value| matches!(value.trim().to_ascii_lowercase().as_str(), "1" | "true" | "on" | "yes"))
+    })
+}
+
+fn get_rate_limiter() -> &'static RateLimiter {
+    RATE_LIMITER.get_or_init(RateLimiter::from_env)
+}
+
+// Origins allowed by ALLOWED_ORIGINS; None keeps the wildcard CORS policy
+fn allowed_origins() -> Option<&'static [String]> {
+    ALLOWED_ORIGINS
+        .get_or_init(|| parse_allowed_origins(std::env::var("ALLOWED_ORIGINS").ok().as_deref()))
+        .as_deref()
+}
+
+fn parse_allowed_origins(raw: Option<&str>) -> Option<Vec<String>> {
+    raw.map(|list| {
+        list.split(',')
+            .map(|origin| origin.trim().trim_end_matches('/').to_string())
+            .filter(|origin| !origin.is_empty())
+            .collect()
+    })
+}
+
+fn get_cache() -> &'static Cache<String, CachedValue> {
+    CACHE.get_or_init(|| {
+        Cache::builder()
+            .max_capacity(1000)
+            .expire_after(PolicyExpiry)
+            .build()
+    })
+}
+
+/// Upstream FPL API endpoints proxied by this service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FplEndpoint {
+    BootstrapStatic,
+    Fixtures,
+    FixturesByGameweek(u32),
+    ElementSummary(u32),
+    LiveEvent(u32),
+    Picks { manager_id: u32, gw: u32 },
+    DreamTeam(u32),
+    ManagerInfo(u32),
+    ManagerTransfers(u32),
+    ManagerHistory(u32),
+    MyTeam(u32),
+    ManagerCup(u32),
+    LeagueStandings { league_id: u32, page: u32 },
+    LeagueStandingsByPhase { league_id: u32, phase: u32 },
+    LeagueNewEntries { league_id: u32, page: u32 },
+    H2hStandings { league_id: u32, page: u32 },
+    H2hMatches { league_id: u32, page: u32 },
+    Transfers,
+}
+
+impl FplEndpoint {
+    fn primary_url(&self) -> String {
+        format!("{}{}", fpl_api_base(), self.path())
+    }
+
+    // Path relative to the FPL API base, including any fixed query parameters
+    fn path(&self) -> String {
+        match self {
+            FplEndpoint::BootstrapStatic => "/bootstrap-static/".to_string(),
+            FplEndpoint::Fixtures => "/fixtures/".to_string(),
+            FplEndpoint::FixturesByGameweek(gw) => format!("/fixtures/?event={}", gw),
+            FplEndpoint::ElementSummary(id) => format!("/element-summary/{}/", id),
+            FplEndpoint::LiveEvent(gw) => format!("/event/{}/live/", gw),
+            FplEndpoint::Picks { manager_id, gw } => format!("/entry/{}/event/{}/picks/", manager_id, gw),
+            FplEndpoint::DreamTeam(gw) => format!("/dream-team/{}/", gw),
+            FplEndpoint::ManagerInfo(id) => format!("/entry/{}/", id),
+            FplEndpoint::ManagerTransfers(id) => format!("/entry/{}/transfers/", id),
+            FplEndpoint::ManagerHistory(id) => format!("/entry/{}/history/", id),
+            FplEndpoint::MyTeam(id) => format!("/my-team/{}/", id),
+            FplEndpoint::ManagerCup(id) => format!("/entry/{}/cup-status/", id),
+            FplEndpoint::Transfers => "/transfers/".to_string(),
+            FplEndpoint::LeagueStandings { league_id, page } => {
+                format!("/leagues-classic/{}/standings/?page_standings={}", league_id, page)
+            }
+            FplEndpoint::LeagueStandingsByPhase { league_id, phase } => {
+                format!("/leagues-classic/{}/standings/?page_standings=1&phase={}", league_id, phase)
+            }
+            FplEndpoint::LeagueNewEntries { league_id, page } => {
+                format!("/leagues-classic/{}/standings/?page_new_entries={}", league_id, page)
+            }
+            FplEndpoint::H2hStandings { league_id, page } => {
+                format!("/leagues-h2h/{}/standings/?page_standings={}", league_id, page)
+            }
+            FplEndpoint::H2hMatches { league_id, page } => {
+                format!("/leagues-h2h-matches/league/{}/?page={}", league_id, page)
+            }
+        }
+    }
+
+    // Static mirror of the endpoint, if the backup API publishes one
+    fn backup_url(&self) -> Option<String> {
+        let base = backup_api_base();
+        match self {
+            FplEndpoint::BootstrapStatic => Some(format!("{}/{}/bootstrap-static.json", base, BACKUP_SEASON)),
+            FplEndpoint::Fixtures => Some(format!("{}/{}/fixtures.json", base, BACKUP_SEASON)),
+            _ => None,
+        }
+    }
+
+    // Key of the embedded backup file served when upstreams are overloaded
+    fn local_backup(&self) -> Option<&'static str> {
+        match self {
+            FplEndpoint::BootstrapStatic => Some("bootstrap-static"),
+            FplEndpoint::Fixtures => Some("fixtures"),
+            FplEndpoint::LiveEvent(_) => Some("live-event"),
+            FplEndpoint::DreamTeam(_) => Some("dream-team"),
+            FplEndpoint::H2hStandings { .. } => Some("h2h-standings"),
+            FplEndpoint::H2hMatches { .. } => Some("h2h-matches"),
+            _ => None,
         }
     }
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Error> {
-    run(handler).await
+// Keys of the embedded backup files, as named by FplEndpoint::local_backup
+const BACKUP_DATA_ENDPOINTS: &[&str] = &["bootstrap-static", "fixtures", "live-event", "dream-team", "h2h-standings", "h2h-matches"];
+
+// Load backup JSON data from embedded files
+pub(crate) fn load_backup_data(endpoint: &str) -> Option<Value> {
+    match endpoint {
+        "bootstrap-static" => {
+            let backup_json = include_str!("../backup-data/bootstrap-static.json");
+            serde_json::from_str(backup_json).ok()
+        },
+        "fixtures" => {
+            let backup_json = include_str!("../backup-data/fixtures.json");
+            serde_json::from_str(backup_json).ok()
+        },
+        "live-event" => {
+            let backup_json = include_str!("../backup-data/live-event.json");
+            serde_json::from_str(backup_json).ok()
+        },
+        "dream-team" => {
+            let backup_json = include_str!("../backup-data/dream-team.json");
+            serde_json::from_str(backup_json).ok()
+        },
+        "h2h-standings" => {
+            let backup_json = include_str!("../backup-data/h2h-standings.json");
+            serde_json::from_str(backup_json).ok()
+        },
+        "h2h-matches" => {
+            let backup_json = include_str!("../backup-data/h2h-matches.json");
+            serde_json::from_str(backup_json).ok()
+        },
+        _ => None
+    }
+}
+
+// A malformed backup file would only show up as a failed fallback during an
+// outage, so refuse to start with one instead
+fn validate_backup_data() {
+    for endpoint in BACKUP_DATA_ENDPOINTS {
+        if load_backup_data(endpoint).is_none() {
+            panic!("backup-data/{}.json is not valid JSON; fix or restore it before deploying", endpoint);
+        }
+    }
+}
+
+// Retry `send` with exponential back-off while `should_retry` accepts the outcome
+async fn retry_with_backoff<T, F, Fut>(mut send: F, should_retry: impl Fn(&T) -> bool) -> T
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = T>,
+{
+    let mut outcome = send().await;
+
+    for retry in 0..PRIMARY_MAX_RETRIES {
+        if !should_retry(&outcome) {
+            break;
+        }
+        let delay = Duration::from_millis(RETRY_BASE_DELAY_MS << retry);
+        warn!("Retrying primary request in {:?} (retry {}/{})", delay, retry + 1, PRIMARY_MAX_RETRIES);
+        tokio::time::sleep(delay).await;
+        outcome = send().await;
+    }
+
+    outcome
+}
+
+// Only network-level errors and 5xx responses are worth retrying, never 4xx
+fn is_retryable(outcome: &Result<reqwest::Response, reqwest::Error>) -> bool {
+    match outcome {
+        Ok(response) => response.status().is_server_error(),
+        Err(_) => true,
+    }
+}
+
+// One upstream request inside an `upstream_fetch` span that records the
+// response status (absent on network errors) and how long the request took
+async fn traced_send(source: &'static str, url: &str, request: reqwest::RequestBuilder) -> reqwest::Result<reqwest::Response> {
+    let span = info_span!(
+        "upstream_fetch",
+        url = %url,
+        source,
+        status = field::Empty,
+        duration_ms = field::Empty,
+    );
+    let started = Instant::now();
+    let outcome = request.send().instrument(span.clone()).await;
+
+    if let Ok(response) = &outcome {
+        span.record("status", response.status().as_u16());
+    }
+    span.record("duration_ms", started.elapsed().as_millis() as u64);
+    outcome
+}
+
+async fn fetch_with_fallback(primary_url: &str, backup_url: Option<&str>, local_backup: Option<&str>) -> Result<FetchResult, ProxyError> {
+    // Test double for offline runs: canned backup data only, never the network
+    if cfg!(feature = "mock") {
+        return local_backup
+            .and_then(load_backup_data)
+            .map(|data| FetchResult {
+                data,
+                last_modified: None,
+                source: DataSource::LocalBackup,
+            })
+            .ok_or(ProxyError::UpstreamUnavailable);
+    }
+
+    let client = get_http_client();
+    let started = Instant::now();
+    let primary_timeout = upstream_timeout().min(Duration::from_secs(PRIMARY_REQUEST_TIMEOUT));
+    let mut is_503_error = false;
+
+    let breaker = get_circuit_breaker();
+
+    // Try primary URL first, unless the circuit breaker is open
+    if breaker.is_open() {
+        warn!("Circuit breaker open, skipping primary URL: {}", primary_url);
+        is_503_error = true;
+    } else {
+        let primary_started = Instant::now();
+        let primary = retry_with_backoff(
+            || traced_send("primary", primary_url, client.get(primary_url).timeout(primary_timeout)),
+            is_retryable,
+        )
+        .await;
+        metrics::observe_upstream("primary", primary_started.elapsed());
+
+        match primary {
+            Ok(response) => {
+                let status = response.status();
+                if status.is_success() {
+                    breaker.record_success();
+                    let last_modified = last_modified_header(&response);
+                    match response.json::<Value>().await {
+                        Ok(data) => return Ok(FetchResult { data, last_modified, source: DataSource::Primary }),
+                        Err(e) => error!("Failed to parse JSON from primary URL {}: {}", primary_url, e),
+                    }
+                } else {
+                    if status.is_server_error() {
+                        breaker.record_failure();
+                    }
+                    if status.as_u16() == 503 {
+                        is_503_error = true;
+                        warn!("Received 503 Service Unavailable from primary URL: {}", primary_url);
+                    } else {
+                        error!("Received non-success status {} from primary URL {}", status, primary_url);
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Failed to fetch from primary URL {}: {}", primary_url, e);
+                breaker.record_failure();
+                // Network errors might indicate overload, treat as potential 503
+                is_503_error = true;
+            }
+        }
+    }
+
+    // Try backup URL if available, within whatever is left of the time budget
+    if let Some(backup_url) = backup_url {
+        let remaining = upstream_timeout().saturating_sub(started.elapsed());
+        let backup_started = Instant::now();
+        let backup = traced_send("backup", backup_url, client.get(backup_url).timeout(remaining)).await;
+        metrics::observe_upstream("backup", backup_started.elapsed());
+
+        match backup {
+            Ok(response) => {
+                let status = response.status();
+                if status.is_success() {
+                    let last_modified = last_modified_header(&response);
+                    match response.json::<Value>().await {
+                        Ok(data) => return Ok(FetchResult { data, last_modified, source: DataSource::BackupUrl }),
+                        Err(e) => error!("Failed to parse JSON from backup URL {}: {}", backup_url, e),
+                    }
+                } else {
+                    if status.as_u16() == 503 {
+                        warn!("Received 503 Service Unavailable from backup URL: {}", backup_url);
+                    } else {
+                        error!("Received non-success status {} from backup URL {}", status, backup_url);
+                    }
+                }
+            }
+            Err(e) => error!("Failed to fetch from backup URL {}: {}", backup_url, e),
+        }
+    }
+
+    // If we encountered 503 errors or network issues, try local backup data
+    if is_503_error {
+        if let Some(backup_endpoint) = local_backup {
+            if let Some(backup_data) = load_backup_data(backup_endpoint) {
+                warn!("Using local backup data for endpoint: {}", backup_endpoint);
+                return Ok(FetchResult {
+                    data: backup_data,
+                    last_modified: None,
+                    source: DataSource::LocalBackup,
+                });
+            }
+        }
+    }
+
+    if is_503_error {
+        Err(ProxyError::UpstreamUnavailable)
+    } else {
+        Err(ProxyError::AllSourcesFailed)
+    }
+}
+
+// Shape of a cache entry as stored in Redis
+#[derive(Serialize, Deserialize)]
+struct KvEntry {
+    data: Value,
+    etag: String,
+    #[serde(default)]
+    last_modified: Option<String>,
+    #[serde(default)]
+    source: DataSource,
+}
+
+// Redis cache configured by KV_URL; None when unset or unreachable
+async fn get_kv_cache() -> Option<&'static KvCache> {
+    KV_CACHE
+        .get_or_init(|| async {
+            let url = std::env::var("KV_URL").ok()?;
+            match tokio::time::timeout(Duration::from_millis(KV_TIMEOUT_MS), KvCache::connect(&url)).await {
+                Ok(Ok(kv)) => Some(kv),
+                Ok(Err(e)) => {
+                    warn!("KV cache unavailable, using in-memory cache only: {}", e);
+                    None
+                }
+                Err(_) => {
+                    warn!("KV cache connection timed out, using in-memory cache only");
+                    None
+                }
+            }
+        })
+        .await
+        .as_ref()
+}
+
+// Look a key up in the in-process cache, then in Redis; Redis hits are copied
+// into the in-process cache for the rest of their remaining TTL
+async fn cache_lookup(cache_key: &str) -> Option<CachedValue> {
+    if let Some(cached) = get_cache().get(cache_key).await {
+        return Some(cached);
+    }
+
+    let kv = get_kv_cache().await?;
+    let (raw, ttl) = match tokio::time::timeout(Duration::from_millis(KV_TIMEOUT_MS), kv.get(cache_key)).await {
+        Ok(Ok(found)) => found?,
+        Ok(Err(e)) => {
+            warn!("KV cache read failed for {}: {}", cache_key, e);
+            return None;
+        }
+        Err(_) => {
+            warn!("KV cache read timed out for {}", cache_key);
+            return None;
+        }
+    };
+
+    let entry: KvEntry = match serde_json::from_str(&raw) {
+        Ok(entry) => entry,
+        Err(e) => {
+            warn!("Ignoring malformed KV cache entry {}: {}", cache_key, e);
+            return None;
+        }
+    };
+    let cached = CachedValue {
+        data: CachedData::Json(entry.data),
+        etag: entry.etag,
+        last_modified: entry.last_modified,
+        source: entry.source,
+        ttl,
+    };
+    get_cache().insert(cache_key.to_string(), cached.clone()).await;
+    Some(cached)
+}
+
+// Write an entry to the in-process cache and, when configured, to Redis
+async fn cache_store(cache_key: String, entry: CachedValue) {
+    if let Some(kv) = get_kv_cache().await {
+        let encoded = entry.data.to_json().and_then(|data| {
+            serde_json::to_string(&KvEntry {
+                data,
+                etag: entry.etag.clone(),
+                last_modified: entry.last_modified.clone(),
+                source: entry.source,
+            }).map_err(ProxyError::JsonParseFailure)
+        });
+        match encoded {
+            Ok(raw) => {
+                match tokio::time::timeout(Duration::from_millis(KV_TIMEOUT_MS), kv.set(&cache_key, &raw, entry.ttl)).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => warn!("KV cache write failed for {}: {}", cache_key, e),
+                    Err(_) => warn!("KV cache write timed out for {}", cache_key),
+                }
+            }
+            Err(e) => warn!("Not writing {} to KV cache: {}", cache_key, e),
+        }
+    }
+
+    get_cache().insert(cache_key, entry).await;
+    metrics::record_cache_operation("store");
+}
+
+// Whether the current request asked to skip cache reads
+fn cache_bypass_requested() -> bool {
+    CACHE_BYPASS.try_with(|bypass| *bypass).unwrap_or(false)
+}
+
+// Cache-Control: no-cache or Pragma: no-cache
+fn wants_fresh_data(headers: &HeaderMap) -> bool {
+    ["cache-control", "pragma"].iter().any(|name| {
+        headers
+            .get_all(*name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|directive| directive.trim().eq_ignore_ascii_case("no-cache"))
+    })
+}
+
+// Count a cache lookup and note its outcome on the request span
+fn record_cache_lookup(hit: bool) {
+    let counter = if hit { &CACHE_HITS } else { &CACHE_MISSES };
+    counter.fetch_add(1, Ordering::Relaxed);
+    metrics::record_cache_operation(if hit { "hit" } else { "miss" });
+    Span::current().record("cache_hit", hit);
+}
+
+pub(crate) async fn get_cached_or_fetch(cache_key: &str, policy: CachePolicy, primary_url: &str, backup_url: Option<&str>, local_backup: Option<&str>) -> Result<Payload, ProxyError> {
+    // Fetch from API with all fallback mechanisms on a miss
+    get_cached_or_compute(cache_key, policy, fetch_with_fallback(primary_url, backup_url, local_backup)).await
+}
+
+// Serve from cache, or await `fetch` and cache its result; the future only runs on a miss
+async fn get_cached_or_compute<F>(cache_key: &str, policy: CachePolicy, fetch: F) -> Result<Payload, ProxyError>
+where
+    F: Future<Output = Result<FetchResult, ProxyError>>,
+{
+    // Check cache first, unless the client asked for fresh data
+    let bypass = cache_bypass_requested();
+    if !bypass {
+        if let Some(cached) = cache_lookup(cache_key).await {
+            record_cache_lookup(true);
+            return Payload::from_cache(cached);
+        }
+    }
+    record_cache_lookup(false);
+
+    let FetchResult { data, last_modified, source } = fetch.await?;
+    let etag = compute_etag(&data);
+
+    // Cache the result with the TTL of its policy
+    let entry = CachedValue {
+        data: CachedData::Json(data.clone()),
+        etag: etag.clone(),
+        last_modified: last_modified.clone(),
+        source,
+        ttl: policy.ttl,
+    };
+    cache_store(cache_key.to_string(), entry).await;
+
+    // A bypassed read still refreshes the cache for everyone else
+    Ok(Payload {
+        data,
+        etag,
+        last_modified,
+        cache_status: if bypass { CacheStatus::Bypass } else { CacheStatus::Miss },
+        source,
+        headers: Vec::new(),
+    })
+}
+
+// Raw value of the first `name=value` pair in a query string
+fn query_param<'a>(query: Option<&'a str>, name: &str) -> Option<&'a str> {
+    query?
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+// Percent-decodes a query value, treating '+' as a space
+fn decode_query_value(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let hex = value.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match hex {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// The client query minus proxy-only parameters, or None when nothing is left
+fn forwarded_query(query: Option<&str>) -> Option<String> {
+    let forwarded: Vec<&str> = query?
+        .split('&')
+        .filter(|pair| {
+            let key = pair.split_once('=').map_or(*pair, |(key, _)| key);
+            !pair.is_empty() && !PROXY_QUERY_PARAMS.contains(&key)
+        })
+        .collect();
+
+    (!forwarded.is_empty()).then(|| forwarded.join("&"))
+}
+
+/// A `?limit=&offset=` window over an array response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pagination {
+    limit: Option<usize>,
+    offset: usize,
+}
+
+impl Pagination {
+    // None when the client sent neither parameter, so responses stay unchanged
+    fn from_query(query: Option<&str>) -> Result<Option<Self>, ProxyError> {
+        let limit = query_param(query, "limit");
+        let offset = query_param(query, "offset");
+        if limit.is_none() && offset.is_none() {
+            return Ok(None);
+        }
+
+        let limit = limit
+            .map(|limit| match limit.parse::<usize>() {
+                Ok(limit) if limit <= MAX_PAGE_LIMIT => Ok(limit),
+                _ => Err(ProxyError::InvalidPathParam(format!("limit: expected 0 to {}", MAX_PAGE_LIMIT))),
+            })
+            .transpose()?;
+        let offset = offset
+            .map(|offset| {
+                offset
+                    .parse::<usize>()
+                    .map_err(|_| ProxyError::InvalidPathParam("offset: expected a non-negative integer".to_string()))
+            })
+            .transpose()?
+            .unwrap_or(0);
+
+        Ok(Some(Self { limit, offset }))
+    }
+
+    // The window of `items`, or an empty page when the offset is past the end
+    fn apply(&self, items: Vec<Value>) -> Vec<Value> {
+        items
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+
+    // RFC 5988 Link header with next/prev pages, if there are any
+    fn link_header(&self, path: &str, query: Option<&str>, total: usize) -> Option<String> {
+        let page_size = self.limit.unwrap_or(total);
+        let base_query = forwarded_query(query);
+        let link = |offset: usize, rel: &str| {
+            let mut target = with_query(path.to_string(), base_query.as_deref());
+            target.push(if base_query.is_some() { '&' } else { '?' });
+            target.push_str(&format!("limit={}&offset={}", page_size, offset));
+            format!("<{}>; rel=\"{}\"", target, rel)
+        };
+
+        let mut links = Vec::new();
+        if page_size > 0 && self.offset + page_size < total {
+            links.push(link(self.offset + page_size, "next"));
+        }
+        if self.offset > 0 && page_size > 0 {
+            links.push(link(self.offset.saturating_sub(page_size), "prev"));
+        }
+        (!links.is_empty()).then(|| links.join(", "))
+    }
+}
+
+// Validate ?limit=&offset= before `fetch` runs, then slice its array response
+async fn paginated<F>(path: &str, query: Option<&str>, fetch: F) -> Result<Payload, ProxyError>
+where
+    F: Future<Output = Result<Payload, ProxyError>>,
+{
+    let pagination = Pagination::from_query(query)?;
+    let payload = fetch.await?;
+    let Some(pagination) = pagination else {
+        return Ok(payload);
+    };
+
+    let items = match payload.data {
+        Value::Array(items) => items,
+        other => return Ok(Payload { data: other, ..payload }),
+    };
+    let total = items.len();
+    let data = Value::Array(pagination.apply(items));
+
+    let mut headers = vec![("x-total-count", total.to_string())];
+    headers.extend(pagination.link_header(path, query, total).map(|link| ("link", link)));
+
+    Ok(Payload {
+        etag: compute_etag(&data),
+        data,
+        headers,
+        ..payload
+    })
+}
+
+// Keys named by ?fields=a,b, or None when the client wants the whole response
+fn requested_fields(query: Option<&str>) -> Option<Vec<String>> {
+    let fields: Vec<String> = decode_query_value(query_param(query, "fields")?)
+        .split(',')
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    (!fields.is_empty()).then_some(fields)
+}
+
+// Keep only the requested top-level keys of an object; unknown keys are
+// simply absent, and non-object responses pass through unchanged
+fn project_fields(data: Value, fields: &[String]) -> Value {
+    match data {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(key, _)| fields.iter().any(|field| field == key))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+// Whether ?pretty or ?pretty=true asked for indented JSON, which is only
+// served in debug mode
+fn pretty_requested(query: Option<&str>, debug_mode: bool) -> Result<bool, ProxyError> {
+    let requested = query.is_some_and(|query| {
+        query.split('&').any(|pair| match pair.split_once('=') {
+            None => pair == "pretty",
+            Some((key, value)) => key == "pretty" && value != "false",
+        })
+    });
+
+    if requested && !debug_mode {
+        return Err(ProxyError::InvalidPathParam("pretty: only available when DEBUG_MODE is enabled".to_string()));
+    }
+    Ok(requested)
+}
+
+// Successful bodies are wrapped with the source of their data, so clients can
+// tell fresh upstream data from a fallback
+fn with_data_source(data: Value, source: DataSource) -> Value {
+    json!({"data": data, "data_source": source})
+}
+
+// The JSONP ?callback= name, which must be a plain JavaScript identifier so it
+// cannot smuggle script into the response
+fn jsonp_callback(query: Option<&str>) -> Result<Option<String>, ProxyError> {
+    let Some(callback) = query_param(query, "callback") else {
+        return Ok(None);
+    };
+
+    let mut chars = callback.chars();
+    let valid = callback.len() <= MAX_JSONP_CALLBACK_LEN
+        && chars.next().is_some_and(|first| first.is_ascii_alphabetic() || first == '_' || first == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if !valid {
+        return Err(ProxyError::InvalidPathParam(format!(
+            "callback: expected a JavaScript identifier of at most {} characters",
+            MAX_JSONP_CALLBACK_LEN
+        )));
+    }
+    Ok(Some(callback.to_string()))
+}
+
+// Reject anything but a plain unsigned integer before it reaches an upstream URL
+fn validate_numeric_id(s: &str) -> Result<u32, ProxyError> {
+    s.parse::<u32>()
+        .map_err(|_| ProxyError::InvalidPathParam(format!("numeric parameter '{}'", s)))
+}
+
+// Admin endpoints require `Authorization: Bearer <ADMIN_TOKEN>`; they are
+// disabled entirely when ADMIN_TOKEN is not configured
+fn require_admin(headers: &HeaderMap) -> Result<(), ProxyError> {
+    let expected = std::env::var("ADMIN_TOKEN").ok().filter(|token| !token.is_empty());
+    let provided = headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match (expected, provided) {
+        (Some(expected), Some(provided)) if expected == provided => Ok(()),
+        _ => Err(ProxyError::Unauthorized),
+    }
+}
+
+// Append the client's raw query string to an upstream URL or cache key
+fn with_query(base: String, query: Option<&str>) -> String {
+    match query.filter(|q| !q.is_empty()) {
+        Some(q) if base.contains('?') => format!("{}&{}", base, q),
+        Some(q) => format!("{}?{}", base, q),
+        None => base,
+    }
+}
+
+async fn handle_cache_status() -> Result<Payload, ProxyError> {
+    let cache = get_cache();
+    cache.run_pending_tasks().await;
+
+    // Approximate memory usage as the serialized size of every cached payload
+    let approximate_bytes: usize = cache
+        .iter()
+        .map(|(key, entry)| key.len() + entry.data.to_json().map_or(0, |data| data.to_string().len()))
+        .sum();
+
+    Ok(Payload::from(json!({
+        "hits": CACHE_HITS.load(Ordering::Relaxed),
+        "misses": CACHE_MISSES.load(Ordering::Relaxed),
+        "entries": cache.entry_count(),
+        "approximate_memory_bytes": approximate_bytes,
+        "timestamp": chrono::Utc::now().to_rfc3339()
+    })))
+}
+
+// Longest key DELETE /cache/:key accepts; real keys are a prefix, a few IDs
+// and at most a short forwarded query string
+const CACHE_KEY_MAX_LEN: usize = 256;
+
+// Whether `key` looks like one built by CachePolicy::cache_key, optionally
+// followed by `?query` from with_query. Anything else is rejected before it
+// reaches the cache or KV, so the endpoint only ever touches keys the proxy
+// itself writes.
+fn is_known_cache_key(key: &str) -> bool {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || "-_.,=&?%+".contains(c);
+    if key.is_empty() || key.len() > CACHE_KEY_MAX_LEN || !key.chars().all(allowed) {
+        return false;
+    }
+
+    CachePolicy::ALL.iter().any(|policy| match key.strip_prefix(policy.prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with('-') || rest.starts_with('?'),
+        None => false,
+    })
+}
+
+// Evicts `key` from the in-memory cache and the KV cache, so the next request
+// refetches it; the key is percent-decoded so `?` can be sent as `%3F`
+async fn handle_cache_evict(key: &str) -> Result<Payload, ProxyError> {
+    let key = decode_query_value(key);
+    if !is_known_cache_key(&key) {
+        return Err(ProxyError::InvalidPathParam("cache key: expected a key built by one of the proxy's cache policies".to_string()));
+    }
+
+    let mut existed = get_cache().remove(&key).await.is_some();
+    if let Some(kv) = get_kv_cache().await {
+        match tokio::time::timeout(Duration::from_millis(KV_TIMEOUT_MS), kv.delete(&key)).await {
+            Ok(Ok(deleted)) => existed |= deleted,
+            Ok(Err(e)) => warn!("KV cache delete failed for {}: {}", key, e),
+            Err(_) => warn!("KV cache delete timed out for {}", key),
+        }
+    }
+
+    if !existed {
+        return Err(ProxyError::NotFound);
+    }
+    info!("Evicted cache key {}", key);
+    Ok(Payload::from(json!({ "evicted": key })))
+}
+
+// "up" when a HEAD request to the URL succeeds within STATUS_CHECK_TIMEOUT, else "down"
+async fn probe_upstream(url: &str) -> &'static str {
+    let response = get_http_client()
+        .head(url)
+        .timeout(Duration::from_secs(STATUS_CHECK_TIMEOUT))
+        .send()
+        .await;
+
+    match response {
+        Ok(response) if response.status().is_success() => "up",
+        Ok(response) => {
+            warn!("Upstream probe of {} returned {}", url, response.status());
+            "down"
+        }
+        Err(e) => {
+            warn!("Upstream probe of {} failed: {}", url, e);
+            "down"
+        }
+    }
+}
+
+// Live upstream reachability; always probes, never cached
+async fn handle_status() -> Result<Payload, ProxyError> {
+    let endpoint = FplEndpoint::BootstrapStatic;
+    let primary_url = endpoint.primary_url();
+    let backup_url = endpoint.backup_url().unwrap_or_else(|| format!("{}/", backup_api_base()));
+
+    let (fpl_api, backup_api) = tokio::join!(probe_upstream(&primary_url), probe_upstream(&backup_url));
+
+    Ok(Payload::from(json!({
+        "fpl_api": fpl_api,
+        "backup_api": backup_api,
+        "cache_entries": get_cache().entry_count(),
+        "features": feature_flags(),
+        "timestamp": chrono::Utc::now().to_rfc3339()
+    })))
+}
+
+// Hand-maintained OpenAPI document, always served as-is and never cached
+fn handle_openapi() -> Result<Payload, ProxyError> {
+    serde_json::from_str::<Value>(include_str!("../openapi.json"))
+        .map(Payload::from)
+        .map_err(ProxyError::JsonParseFailure)
+}
+
+// Typed bootstrap-static entry from cache or upstream, and how it was served
+async fn bootstrap_static_entry(query: Option<&str>) -> Result<(CachedValue, CacheStatus), ProxyError> {
+    let endpoint = FplEndpoint::BootstrapStatic;
+    let primary_url = with_query(endpoint.primary_url(), query);
+    let backup_url = endpoint.backup_url();
+    let policy = CachePolicy::BOOTSTRAP_STATIC;
+    let cache_key = with_query(policy.cache_key(&[]), query);
+
+    let bypass = cache_bypass_requested();
+    if let Some(cached) = if bypass { None } else { cache_lookup(&cache_key).await } {
+        record_cache_lookup(true);
+        if let CachedData::Json(data) = cached.data {
+            // Entries read back from the KV cache are untyped; type them once
+            let bootstrap: BootstrapStatic = serde_json::from_value(data).map_err(ProxyError::JsonParseFailure)?;
+            let typed = CachedValue {
+                data: CachedData::Bootstrap(Arc::new(bootstrap)),
+                ..cached
+            };
+            get_cache().insert(cache_key, typed.clone()).await;
+            return Ok((typed, CacheStatus::Hit));
+        }
+        return Ok((cached, CacheStatus::Hit));
+    }
+    record_cache_lookup(false);
+
+    // Deserialize into the typed model so malformed upstream data is rejected
+    // instead of cached
+    let fetched = fetch_with_fallback(&primary_url, backup_url.as_deref(), endpoint.local_backup()).await?;
+    let bootstrap: BootstrapStatic = serde_json::from_value(fetched.data).map_err(ProxyError::JsonParseFailure)?;
+    let data = serde_json::to_value(&bootstrap).map_err(ProxyError::JsonParseFailure)?;
+
+    let entry = CachedValue {
+        data: CachedData::Bootstrap(Arc::new(bootstrap)),
+        etag: compute_etag(&data),
+        last_modified: fetched.last_modified,
+        source: fetched.source,
+        ttl: policy.ttl,
+    };
+    cache_store(cache_key, entry.clone()).await;
+
+    Ok((entry, if bypass { CacheStatus::Bypass } else { CacheStatus::Miss }))
+}
+
+// Typed bootstrap-static for the endpoints derived from it, and its source
+async fn bootstrap_static() -> Result<(Arc<BootstrapStatic>, DataSource), ProxyError> {
+    let entry = bootstrap_static_entry(None).await?.0;
+    let bootstrap = match entry.data {
+        CachedData::Bootstrap(bootstrap) => bootstrap,
+        CachedData::Json(data) => serde_json::from_value(data).map(Arc::new).map_err(ProxyError::JsonParseFailure)?,
+    };
+    Ok((bootstrap, entry.source))
+}
+
+async fn handle_bootstrap_static(query: Option<&str>) -> Result<Payload, ProxyError> {
+    let (entry, cache_status) = bootstrap_static_entry(query).await?;
+
+    // Re-serialize the typed model for the wire
+    Ok(Payload {
+        cache_status,
+        ..Payload::from_cache(entry)?
+    })
+}
+
+// One top-level array of bootstrap-static, cached on its own with the bootstrap TTL
+async fn handle_bootstrap_field(field: &'static str, policy: CachePolicy) -> Result<Payload, ProxyError> {
+    let extract = async {
+        let bootstrap = handle_bootstrap_static(None).await?;
+        Ok(FetchResult {
+            data: array_field(&bootstrap.data, field),
+            last_modified: bootstrap.last_modified,
+            source: bootstrap.source,
+        })
+    };
+
+    get_cached_or_compute(&policy.cache_key(&[]), policy, extract).await
+}
+
+async fn handle_teams() -> Result<Payload, ProxyError> {
+    handle_bootstrap_field("teams", CachePolicy::TEAMS).await
+}
+
+async fn handle_events() -> Result<Payload, ProxyError> {
+    handle_bootstrap_field("events", CachePolicy::EVENTS).await
+}
+
+// Positions (1=GKP, 2=DEF, 3=MID, 4=FWD) with their names and squad limits
+async fn handle_element_types() -> Result<Payload, ProxyError> {
+    handle_bootstrap_field("element_types", CachePolicy::ELEMENT_TYPES).await
+}
+
+// The single gameweek matching `flag`, e.g. the one with is_current set
+async fn handle_flagged_event(flag: fn(&Event) -> bool) -> Result<Payload, ProxyError> {
+    let (bootstrap, source) = bootstrap_static().await?;
+    let event = bootstrap.events.iter().find(|event| flag(event)).ok_or(ProxyError::NotFound)?;
+    let data = serde_json::to_value(event).map_err(ProxyError::JsonParseFailure)?;
+    Ok(Payload { source, ..Payload::from(data) })
+}
+
+async fn handle_current_gameweek() -> Result<Payload, ProxyError> {
+    let policy = CachePolicy::CURRENT_GW;
+    let current = async {
+        let (bootstrap, source) = bootstrap_static().await?;
+        let event = bootstrap.current_event().ok_or(ProxyError::NotFound)?;
+        Ok(FetchResult {
+            data: json!({
+                "current_gw": event.id,
+                "deadline": event.deadline_time
+            }),
+            last_modified: None,
+            source,
+        })
+    };
+
+    get_cached_or_compute(&policy.cache_key(&[]), policy, current).await
+}
+
+// One {season_id, name, total_players} entry per bootstrap-static phase
+fn season_history(bootstrap: &BootstrapStatic) -> Value {
+    let total_players = bootstrap.extra.get("total_players").cloned().unwrap_or(Value::Null);
+    let phases = bootstrap.extra.get("phases").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+
+    phases
+        .iter()
+        .map(|phase| {
+            json!({
+                "season_id": phase.get("id"),
+                "name": phase.get("name"),
+                "total_players": total_players
+            })
+        })
+        .collect()
+}
+
+async fn handle_season_history() -> Result<Payload, ProxyError> {
+    let policy = CachePolicy::SEASON_HISTORY;
+    let history = async {
+        let (bootstrap, source) = bootstrap_static().await?;
+        Ok(FetchResult {
+            data: season_history(&bootstrap),
+            last_modified: None,
+            source,
+        })
+    };
+
+    get_cached_or_compute(&policy.cache_key(&[]), policy, history).await
+}
+
+async fn handle_fixtures(query: Option<&str>) -> Result<Payload, ProxyError> {
+    let endpoint = FplEndpoint::Fixtures;
+    let primary_url = with_query(endpoint.primary_url(), query);
+    let backup_url = endpoint.backup_url();
+    let policy = CachePolicy::FIXTURES;
+
+    get_cached_or_fetch(&with_query(policy.cache_key(&[]), query), policy, &primary_url, backup_url.as_deref(), endpoint.local_backup()).await
+}
+
+// Fixtures that have not kicked off, soonest first; unscheduled ones (no kickoff_time) last
+fn upcoming_fixtures(fixtures: Vec<Value>) -> Vec<Value> {
+    let not_true = |fixture: &Value, field| !fixture.get(field).and_then(Value::as_bool).unwrap_or(false);
+    let mut upcoming: Vec<Value> = fixtures
+        .into_iter()
+        .filter(|fixture| not_true(fixture, "finished") && not_true(fixture, "started"))
+        .collect();
+
+    sort_by_kickoff(&mut upcoming);
+    upcoming
+}
+
+// Soonest kickoff first, fixtures without a kickoff_time last
+fn sort_by_kickoff(fixtures: &mut [Value]) {
+    // RFC 3339 UTC timestamps sort chronologically as strings
+    fixtures.sort_by_cached_key(|fixture| {
+        let kickoff = fixture.get("kickoff_time").and_then(Value::as_str).map(str::to_string);
+        (kickoff.is_none(), kickoff)
+    });
+}
+
+// Unfinished fixtures of `team`, soonest first, each with `is_home` and the
+// `difficulty` rating of the match for that team
+fn team_upcoming_fixtures(fixtures: Vec<Value>, team: u32) -> Vec<Value> {
+    let mut upcoming: Vec<Value> = fixtures
+        .into_iter()
+        .filter(|fixture| !fixture.get("finished").and_then(Value::as_bool).unwrap_or(false))
+        .filter_map(|mut fixture| {
+            let side = |field| fixture.get(field).and_then(Value::as_u64) == Some(team.into());
+            let is_home = match (side("team_h"), side("team_a")) {
+                (true, _) => true,
+                (_, true) => false,
+                _ => return None,
+            };
+            let difficulty = fixture
+                .get(if is_home { "team_h_difficulty" } else { "team_a_difficulty" })
+                .cloned()
+                .unwrap_or(Value::Null);
+            fixture["is_home"] = Value::Bool(is_home);
+            fixture["difficulty"] = difficulty;
+            Some(fixture)
+        })
+        .collect();
+
+    sort_by_kickoff(&mut upcoming);
+    upcoming
+}
+
+// Joins bootstrap-static (for the player's team) with the cached fixtures
+async fn handle_player_upcoming_fixtures(id: &str) -> Result<Payload, ProxyError> {
+    let id = validate_numeric_id(id)?;
+    let policy = CachePolicy::PLAYER_FIXTURES;
+    let upcoming = async {
+        let ((bootstrap, bootstrap_source), fixtures) = tokio::try_join!(bootstrap_static(), handle_fixtures(None))?;
+        let team = bootstrap
+            .elements
+            .iter()
+            .find(|player| player.id == id)
+            .map(|player| player.team)
+            .ok_or(ProxyError::NotFound)?;
+        let all = match fixtures.data {
+            Value::Array(all) => all,
+            _ => Vec::new(),
+        };
+        Ok(FetchResult {
+            data: Value::Array(team_upcoming_fixtures(all, team)),
+            last_modified: fixtures.last_modified,
+            source: fixtures.source.max(bootstrap_source),
+        })
+    };
+
+    get_cached_or_compute(&policy.cache_key(&[&id]), policy, upcoming).await
+}
+
+async fn handle_upcoming_fixtures() -> Result<Payload, ProxyError> {
+    let policy = CachePolicy::FIXTURES_UPCOMING;
+    let upcoming = async {
+        let fixtures = handle_fixtures(None).await?;
+        let all = match fixtures.data {
+            Value::Array(all) => all,
+            _ => Vec::new(),
+        };
+        Ok(FetchResult {
+            data: Value::Array(upcoming_fixtures(all)),
+            last_modified: fixtures.last_modified,
+            source: fixtures.source,
+        })
+    };
+
+    get_cached_or_compute(&policy.cache_key(&[]), policy, upcoming).await
+}
+
+// Fixtures re-serialized through the typed model, each with its computed outcome
+async fn handle_fixtures_typed(query: Option<&str>) -> Result<Payload, ProxyError> {
+    let fixtures = handle_fixtures(query).await?;
+    let typed: Vec<Fixture> = serde_json::from_value(fixtures.data).map_err(ProxyError::JsonParseFailure)?;
+
+    let data = typed
+        .iter()
+        .map(|fixture| {
+            let mut value = serde_json::to_value(fixture)?;
+            value["outcome"] = serde_json::to_value(fixture.outcome())?;
+            Ok(value)
+        })
+        .collect::<Result<Vec<Value>, serde_json::Error>>()
+        .map_err(ProxyError::JsonParseFailure)?;
+
+    let data = Value::Array(data);
+    Ok(Payload {
+        etag: compute_etag(&data),
+        data,
+        last_modified: fixtures.last_modified,
+        source: fixtures.source,
+        cache_status: fixtures.cache_status,
+        headers: Vec::new(),
+    })
+}
+
+async fn handle_fixtures_by_gameweek(gw: &str, query: Option<&str>) -> Result<Payload, ProxyError> {
+    let gw = validate_numeric_id(gw)?;
+    let endpoint = FplEndpoint::FixturesByGameweek(gw);
+    let url = with_query(endpoint.primary_url(), query);
+    let policy = CachePolicy::FIXTURES_GAMEWEEK;
+
+    get_cached_or_fetch(&with_query(policy.cache_key(&[&gw]), query), policy, &url, None, endpoint.local_backup()).await
+}
+
+// Finished fixtures only, which are the ones carrying final scores
+fn finished_fixtures(fixtures: Vec<Value>) -> Vec<Value> {
+    fixtures
+        .into_iter()
+        .filter(|fixture| fixture.get("finished").and_then(Value::as_bool) == Some(true))
+        .collect()
+}
+
+async fn handle_gw_results(gw: &str) -> Result<Payload, ProxyError> {
+    let gw = validate_gameweek(gw)?;
+    let policy = CachePolicy::GW_RESULTS;
+    let results = async {
+        let fixtures = handle_fixtures_by_gameweek(&gw.to_string(), None).await?;
+        let all = match fixtures.data {
+            Value::Array(all) => all,
+            _ => Vec::new(),
+        };
+        Ok(FetchResult {
+            data: Value::Array(finished_fixtures(all)),
+            last_modified: fixtures.last_modified,
+            source: fixtures.source,
+        })
+    };
+
+    get_cached_or_compute(&policy.cache_key(&[&gw]), policy, results).await
+}
+
+async fn handle_element_summary(id: &str, query: Option<&str>) -> Result<Payload, ProxyError> {
+    let endpoint = FplEndpoint::ElementSummary(validate_numeric_id(id)?);
+    let url = with_query(endpoint.primary_url(), query);
+    fetch_with_fallback(&url, None, None).await.map(Payload::from)
+}
+
+// Past-season history of a player; immutable, so cached for longer than the summary
+async fn handle_player_history(id: &str, query: Option<&str>) -> Result<Payload, ProxyError> {
+    let id = validate_numeric_id(id)?;
+    let policy = CachePolicy::PLAYER_HISTORY;
+    let history = async {
+        let summary = handle_element_summary(&id.to_string(), query).await?;
+        Ok(FetchResult {
+            data: array_field(&summary.data, "history_past"),
+            last_modified: summary.last_modified,
+            source: summary.source,
+        })
+    };
+
+    get_cached_or_compute(&with_query(policy.cache_key(&[&id]), query), policy, history).await
+}
+
+// The current season's fixture-by-fixture stats, i.e. the summary's `history`
+async fn handle_player_stats(id: &str) -> Result<Payload, ProxyError> {
+    let id = validate_numeric_id(id)?;
+    let policy = CachePolicy::PLAYER_STATS;
+    let stats = async {
+        let summary = handle_element_summary(&id.to_string(), None).await?;
+        Ok(FetchResult {
+            data: array_field(&summary.data, "history"),
+            last_modified: summary.last_modified,
+            source: summary.source,
+        })
+    };
+
+    get_cached_or_compute(&policy.cache_key(&[&id]), policy, stats).await
+}
+
+// Mean of `stat` over the last `count` fixtures of `history`, None without fixtures
+fn recent_average(history: &[Value], count: usize, stat: &str) -> Option<f64> {
+    let recent = &history[history.len().saturating_sub(count)..];
+    // Points and bps go negative, e.g. after a red card or an own goal
+    let total: f64 = recent.iter().filter_map(|fixture| fixture[stat].as_f64()).sum();
+    (!recent.is_empty()).then(|| total / recent.len() as f64)
+}
+
+// Averages over the player's last FORM_RECENT_FIXTURES fixtures, with a trend
+// from comparing their points average to that of the last FORM_BASELINE_FIXTURES
+fn form_trend(history: &[Value]) -> Value {
+    let recent_avg_points = recent_average(history, FORM_RECENT_FIXTURES, "total_points");
+    let baseline_avg_points = recent_average(history, FORM_BASELINE_FIXTURES, "total_points");
+    let trend = match (recent_avg_points, baseline_avg_points) {
+        (Some(recent), Some(baseline)) if recent > baseline + FORM_TREND_TOLERANCE => "improving",
+        (Some(recent), Some(baseline)) if recent < baseline - FORM_TREND_TOLERANCE => "declining",
+        _ => "stable",
+    };
+
+    let gws: Vec<Value> = history[history.len().saturating_sub(FORM_RECENT_FIXTURES)..]
+        .iter()
+        .map(|fixture| {
+            json!({
+                "round": fixture["round"],
+                "total_points": fixture["total_points"],
+                "bps": fixture["bps"],
+                "minutes": fixture["minutes"]
+            })
+        })
+        .collect();
+
+    json!({
+        "recent_avg_points": recent_avg_points,
+        "recent_avg_bps": recent_average(history, FORM_RECENT_FIXTURES, "bps"),
+        "recent_avg_minutes": recent_average(history, FORM_RECENT_FIXTURES, "minutes"),
+        "baseline_avg_points": baseline_avg_points,
+        "gws": gws,
+        "trend": trend
+    })
+}
+
+async fn handle_form_trend(id: &str) -> Result<Payload, ProxyError> {
+    let id = validate_numeric_id(id)?;
+    let policy = CachePolicy::FORM_TREND;
+    let trend = async {
+        let summary = handle_element_summary(&id.to_string(), None).await?;
+        let history = array_field(&summary.data, "history");
+        Ok(FetchResult {
+            data: form_trend(history.as_array().map_or(&[], Vec::as_slice)),
+            last_modified: summary.last_modified,
+            source: summary.source,
+        })
+    };
+
+    get_cached_or_compute(&policy.cache_key(&[&id]), policy, trend).await
+}
+
+// Price changes between consecutive fixtures of a summary's `history`, whose
+// `value` is the price at the fixture in tenths of a million
+fn price_changes(history: &[Value]) -> Vec<Value> {
+    let priced: Vec<(&Value, i64)> = history
+        .iter()
+        .filter_map(|fixture| Some((&fixture["round"], fixture["value"].as_i64()?)))
+        .collect();
+
+    priced
+        .windows(2)
+        .filter(|pair| pair[0].1 != pair[1].1)
+        .map(|pair| {
+            let ((_, old_price), (gw, new_price)) = (pair[0], pair[1]);
+            json!({"gw": gw, "old_price": old_price, "new_price": new_price, "change": new_price - old_price})
+        })
+        .collect()
+}
+
+async fn handle_price_history(id: &str) -> Result<Payload, ProxyError> {
+    let id = validate_numeric_id(id)?;
+    let policy = CachePolicy::PRICE_HISTORY;
+    let changes = async {
+        let summary = handle_element_summary(&id.to_string(), None).await?;
+        let history = array_field(&summary.data, "history");
+        Ok(FetchResult {
+            data: Value::Array(price_changes(history.as_array().map_or(&[], Vec::as_slice))),
+            last_modified: summary.last_modified,
+            source: summary.source,
+        })
+    };
+
+    get_cached_or_compute(&policy.cache_key(&[&id]), policy, changes).await
+}
+
+// selected_by_percent snapshots of one player, oldest first. The FPL API only
+// exposes the current value, so this history is built up from the
+// bootstrap-static data seen by this instance and starts afresh on a cold start.
+#[derive(Debug, Clone, Default)]
+struct OwnershipHistory {
+    points: VecDeque<(chrono::DateTime<chrono::Utc>, f64)>,
+}
+
+impl OwnershipHistory {
+    // Appends a snapshot, dropping the oldest beyond OWNERSHIP_HISTORY_MAX_POINTS.
+    // bootstrap-static is cached, so a value equal to the latest adds nothing.
+    fn record(&mut self, at: chrono::DateTime<chrono::Utc>, ownership: f64) {
+        if self.points.back().is_some_and(|(_, latest)| *latest == ownership) {
+            return;
+        }
+        if self.points.len() == OWNERSHIP_HISTORY_MAX_POINTS {
+            self.points.pop_front();
+        }
+        self.points.push_back((at, ownership));
+    }
+
+    fn to_json(&self) -> Value {
+        self.points
+            .iter()
+            .map(|(at, ownership)| json!({ "timestamp": at.to_rfc3339(), "selected_by_percent": ownership }))
+            .collect()
+    }
+}
+
+fn ownership_history() -> &'static Cache<u32, OwnershipHistory> {
+    OWNERSHIP_HISTORY.get_or_init(|| Cache::new(1000))
+}
+
+// Records `ownership` for player `id` and returns the player's history. Only
+// primary data is a real observation: the backup URL and the embedded backup
+// hold fixed values, so recording those would make ownership jump back and
+// forth during an outage.
+async fn record_ownership(id: u32, ownership: Option<f64>, source: DataSource) -> OwnershipHistory {
+    let observed = ownership.filter(|_| source == DataSource::Primary);
+    let now = chrono::Utc::now();
+    ownership_history()
+        .entry(id)
+        .and_upsert_with(|entry| async move {
+            let mut history = entry.map(moka::Entry::into_value).unwrap_or_default();
+            if let Some(ownership) = observed {
+                history.record(now, ownership);
+            }
+            history
+        })
+        .await
+        .into_value()
+}
+
+async fn handle_ownership_trend(id: &str) -> Result<Payload, ProxyError> {
+    let id = validate_numeric_id(id)?;
+    let (bootstrap, source) = bootstrap_static().await?;
+    let player = bootstrap.elements.iter().find(|player| player.id == id).ok_or(ProxyError::NotFound)?;
+    let history = record_ownership(id, player.selected_by_percent.parse::<f64>().ok(), source).await;
+
+    Ok(Payload {
+        source,
+        ..Payload::from(json!({
+            "player_id": id,
+            "web_name": player.web_name,
+            "history": history.to_json()
+        }))
+    })
+}
+
+// The player's remaining fixtures, i.e. the summary's `fixtures`; upstream
+// already tags each with `is_home` and `difficulty`
+async fn handle_player_fixtures(id: &str) -> Result<Payload, ProxyError> {
+    let id = validate_numeric_id(id)?;
+    let policy = CachePolicy::ELEMENT_FIXTURES;
+    let fixtures = async {
+        let summary = handle_element_summary(&id.to_string(), None).await?;
+        Ok(FetchResult {
+            data: array_field(&summary.data, "fixtures"),
+            last_modified: summary.last_modified,
+            source: summary.source,
+        })
+    };
+
+    get_cached_or_compute(&policy.cache_key(&[&id]), policy, fixtures).await
+}
+
+// Players whose web name or full name contains `query`, ignoring case
+fn search_players<'a>(bootstrap: &'a BootstrapStatic, query: &str) -> Vec<&'a Player> {
+    let needle = query.to_lowercase();
+    bootstrap
+        .elements
+        .iter()
+        .filter(|player| {
+            player.web_name.to_lowercase().contains(&needle)
+                || format!("{} {}", player.first_name, player.second_name).to_lowercase().contains(&needle)
+        })
+        .take(PLAYER_SEARCH_MAX)
+        .collect()
+}
+
+// Name search over the cached bootstrap-static players; no upstream call of its own
+async fn handle_player_search(query: &str) -> Result<Payload, ProxyError> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Err(ProxyError::InvalidPathParam("q: expected a player name".to_string()));
+    }
+
+    let (bootstrap, source) = bootstrap_static().await?;
+    let players = serde_json::to_value(search_players(&bootstrap, query)).map_err(ProxyError::JsonParseFailure)?;
+    Ok(Payload { source, ..Payload::from(players) })
+}
+
+// A non-negative integer query parameter, or None when absent
+fn u32_query_param(query: Option<&str>, name: &str) -> Result<Option<u32>, ProxyError> {
+    query_param(query, name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| ProxyError::InvalidPathParam(format!("{}: expected a non-negative integer", name)))
+        })
+        .transpose()
+}
+
+// Players within the price bounds (inclusive, in tenths of a million) of the
+// given position and club, in bootstrap-static order
+fn filter_players(bootstrap: &BootstrapStatic, min_price: Option<u32>, max_price: Option<u32>, position: Option<u32>, team: Option<u32>) -> Vec<&Player> {
+    bootstrap
+        .elements
+        .iter()
+        .filter(|player| {
+            min_price.is_none_or(|min| player.now_cost >= min)
+                && max_price.is_none_or(|max| player.now_cost <= max)
+                && position.is_none_or(|position| player.element_type == position)
+                && team.is_none_or(|team| player.team == team)
+        })
+        .take(PLAYERS_MAX)
+        .collect()
+}
+
+// Filtered listing of the bootstrap-static players, cached per combination of filters
+async fn handle_players(min_price: Option<u32>, max_price: Option<u32>, position: Option<u32>, team: Option<u32>) -> Result<Payload, ProxyError> {
+    if let (Some(min), Some(max)) = (min_price, max_price) {
+        if min > max {
+            return Err(ProxyError::InvalidPathParam("min_price: must not exceed max_price".to_string()));
+        }
+    }
+
+    // The filters in a fixed order, so equivalent query strings share an entry
+    let fingerprint = [("min_price", min_price), ("max_price", max_price), ("position", position), ("team", team)]
+        .iter()
+        .filter_map(|(name, value)| value.map(|value| format!("{}={}", name, value)))
+        .collect::<Vec<_>>()
+        .join("&");
+    let policy = CachePolicy::PLAYERS;
+    let players = async {
+        let (bootstrap, source) = bootstrap_static().await?;
+        let players = filter_players(&bootstrap, min_price, max_price, position, team);
+        Ok(FetchResult {
+            data: serde_json::to_value(players).map_err(ProxyError::JsonParseFailure)?,
+            last_modified: None,
+            source,
+        })
+    };
+
+    get_cached_or_compute(&with_query(policy.cache_key(&[]), Some(&fingerprint)), policy, players).await
+}
+
+// Element summary of one player, tagged with its ID for the bulk fan-out
+async fn fetch_element_summary(id: u32) -> (u32, Result<Value, ProxyError>) {
+    let url = FplEndpoint::ElementSummary(id).primary_url();
+    (id, fetch_with_fallback(&url, None, None).await.map(|fetched| fetched.data))
+}
+
+// 1 to BULK_MAX_IDS player IDs, sorted and without duplicates
+fn unique_bulk_ids(ids: Vec<u32>) -> Result<Vec<u32>, ProxyError> {
+    if ids.is_empty() || ids.len() > BULK_MAX_IDS {
+        return Err(ProxyError::InvalidPathParam(format!("ID list: expected 1 to {} IDs", BULK_MAX_IDS)));
+    }
+
+    let mut unique = ids;
+    unique.sort_unstable();
+    unique.dedup();
+    Ok(unique)
+}
+
+async fn handle_element_summary_bulk(ids: Vec<u32>) -> Result<Payload, ProxyError> {
+    let unique = unique_bulk_ids(ids)?;
+
+    // Keep at most BULK_CONCURRENCY upstream requests in flight
+    let mut pending = unique.into_iter();
+    let mut in_flight: FuturesUnordered<_> = pending.by_ref().take(BULK_CONCURRENCY).map(fetch_element_summary).collect();
+    let mut summaries = Map::new();
+
+    while let Some((id, result)) = in_flight.next().await {
+        summaries.insert(id.to_string(), result?);
+        if let Some(next) = pending.next() {
+            in_flight.push(fetch_element_summary(next));
+        }
+    }
+
+    Ok(Payload::from(Value::Object(summaries)))
+}
+
+// One NDJSON line of /element-summary/ndjson. A failed player gets an error
+// line instead of failing the whole response, so the format stays usable once
+// the body can be streamed and the 200 is sent before the last player.
+fn element_summary_line(id: u32, result: Result<Value, ProxyError>) -> String {
+    let line = match result {
+        Ok(data) => json!({ "id": id, "data": data }),
+        Err(e) => json!({ "id": id, "error": e.to_string() }),
+    };
+    format!("{}\n", line)
+}
+
+// The NDJSON body of /element-summary/ndjson, one line per player in the order
+// the upstream responses complete. The body is buffered, not streamed:
+// vercel_runtime 1.1 hands the runtime one complete response and its Body has
+// no streaming variant, so clients get every line once the last fetch is done.
+async fn element_summary_ndjson(ids: Vec<u32>) -> Result<String, ProxyError> {
+    let unique = unique_bulk_ids(ids)?;
+    let mut summaries = futures::stream::iter(unique)
+        .map(fetch_element_summary)
+        .buffer_unordered(BULK_CONCURRENCY);
+
+    let mut body = String::new();
+    while let Some((id, result)) = summaries.next().await {
+        body.push_str(&element_summary_line(id, result));
+    }
+    Ok(body)
+}
+
+// Player IDs for the bulk endpoint: a JSON array body for POST, `?ids=1,2,3` otherwise
+fn parse_bulk_ids(method: &Method, body: &[u8], query: Option<&str>) -> Result<Vec<u32>, ProxyError> {
+    if method == Method::POST {
+        return serde_json::from_slice(body)
+            .map_err(|_| ProxyError::InvalidPathParam("request body: expected a JSON array of player IDs".to_string()));
+    }
+
+    let ids = query_param(query, "ids")
+        .ok_or_else(|| ProxyError::InvalidPathParam("query: missing 'ids' parameter".to_string()))?;
+    ids.replace("%2C", ",")
+        .replace("%2c", ",")
+        .split(',')
+        .map(|id| validate_numeric_id(id.trim()))
+        .collect()
+}
+
+async fn handle_live_event(gw: &str, query: Option<&str>) -> Result<Payload, ProxyError> {
+    let gw = validate_numeric_id(gw)?;
+    let endpoint = FplEndpoint::LiveEvent(gw);
+    let url = with_query(endpoint.primary_url(), query);
+    let policy = CachePolicy::LIVE_EVENT;
+
+    let payload = get_cached_or_fetch(&with_query(policy.cache_key(&[&gw]), query), policy, &url, None, endpoint.local_backup()).await?;
+
+    // Round-trip through the typed model so schema drift fails loudly instead of reaching clients
+    let live: LiveEvent = serde_json::from_value(payload.data).map_err(ProxyError::JsonParseFailure)?;
+    let data = serde_json::to_value(&live).map_err(ProxyError::JsonParseFailure)?;
+    Ok(Payload { data, ..payload })
+}
+
+// Validate a gameweek number, 1 to 38
+fn validate_gameweek(gw: &str) -> Result<u32, ProxyError> {
+    match validate_numeric_id(gw)? {
+        gw @ 1..=GAMEWEEK_COUNT => Ok(gw),
+        _ => Err(ProxyError::InvalidPathParam(format!("gameweek: expected 1 to {}", GAMEWEEK_COUNT))),
+    }
+}
+
+// Validate a club ID, 1 to 20
+fn validate_team_id(team_id: &str) -> Result<u32, ProxyError> {
+    match validate_numeric_id(team_id)? {
+        team_id @ 1..=TEAM_COUNT => Ok(team_id),
+        _ => Err(ProxyError::InvalidPathParam(format!("team ID: expected 1 to {}", TEAM_COUNT))),
+    }
+}
+
+// A club's players, goalkeepers first, most expensive first within each
+// position, ties broken by player ID
+fn team_players(bootstrap: &BootstrapStatic, team_id: u32) -> Vec<&Player> {
+    let mut players: Vec<&Player> = bootstrap.elements.iter().filter(|player| player.team == team_id).collect();
+    players.sort_by_key(|player| (player.element_type, std::cmp::Reverse(player.now_cost), player.id));
+    players
+}
+
+async fn handle_team_players(team_id: &str) -> Result<Payload, ProxyError> {
+    let team_id = validate_team_id(team_id)?;
+    let policy = CachePolicy::TEAM_PLAYERS;
+    let players = async {
+        let (bootstrap, source) = bootstrap_static().await?;
+        Ok(FetchResult {
+            data: serde_json::to_value(team_players(&bootstrap, team_id)).map_err(ProxyError::JsonParseFailure)?,
+            last_modified: None,
+            source,
+        })
+    };
+
+    get_cached_or_compute(&policy.cache_key(&[&team_id]), policy, players).await
+}
+
+// The players most transferred in and out, by the bootstrap-static `field`
+// (`transfers_in_event` or `transfers_out_event`), ties broken by player ID
+fn most_transferred(bootstrap: &BootstrapStatic, field: &str) -> Vec<Value> {
+    let count = |player: &Player| player.extra.get(field).and_then(Value::as_u64).unwrap_or(0);
+    let mut players: Vec<&Player> = bootstrap.elements.iter().collect();
+    players.sort_by_key(|player| (std::cmp::Reverse(count(player)), player.id));
+
+    players
+        .into_iter()
+        .take(GW_TRANSFERS_COUNT)
+        .map(|player| {
+            json!({
+                "player_id": player.id,
+                "web_name": player.web_name,
+                "team": player.team,
+                "transfers": count(player)
+            })
+        })
+        .collect()
+}
+
+// bootstrap-static only counts transfers for the current gameweek, so other
+// gameweeks have nothing to report
+fn gameweek_transfers(bootstrap: &BootstrapStatic, gw: u32) -> Option<Value> {
+    if bootstrap.current_event()?.id != gw {
+        return None;
+    }
+    Some(json!({
+        "gw": gw,
+        "transfers_in": most_transferred(bootstrap, "transfers_in_event"),
+        "transfers_out": most_transferred(bootstrap, "transfers_out_event")
+    }))
+}
+
+async fn handle_gw_transfers(gw: &str) -> Result<Payload, ProxyError> {
+    let gw = validate_gameweek(gw)?;
+    let policy = CachePolicy::GW_TRANSFERS;
+    let transfers = async {
+        let (bootstrap, source) = bootstrap_static().await?;
+        Ok(FetchResult {
+            data: gameweek_transfers(&bootstrap, gw).ok_or(ProxyError::NotFound)?,
+            last_modified: None,
+            source,
+        })
+    };
+
+    get_cached_or_compute(&policy.cache_key(&[&gw]), policy, transfers).await
+}
+
+// Players awarded bonus in a gameweek, most bonus first, with their name and club
+fn gameweek_bonus(live: &LiveEvent, bootstrap: &BootstrapStatic) -> Vec<Value> {
+    let mut awarded: Vec<(u32, u32)> = live
+        .elements
+        .iter()
+        .filter_map(|element| element.stats.bonus.filter(|bonus| *bonus > 0).map(|bonus| (element.id, bonus)))
+        .collect();
+    awarded.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    awarded
+        .into_iter()
+        .map(|(id, bonus)| {
+            let player = bootstrap.elements.iter().find(|player| player.id == id);
+            json!({
+                "player_id": id,
+                "web_name": player.map(|player| &player.web_name),
+                "team": player.map(|player| player.team),
+                "bonus": bonus
+            })
+        })
+        .collect()
+}
+
+async fn handle_gw_bonus(gw: &str) -> Result<Payload, ProxyError> {
+    let gw = validate_gameweek(gw)?;
+    let policy = CachePolicy::GW_BONUS;
+    let bonus = async {
+        let event = gw.to_string();
+        let (payload, (bootstrap, bootstrap_source)) = tokio::try_join!(handle_live_event(&event, None), bootstrap_static())?;
+        let live: LiveEvent = serde_json::from_value(payload.data).map_err(ProxyError::JsonParseFailure)?;
+        Ok(FetchResult {
+            data: Value::Array(gameweek_bonus(&live, &bootstrap)),
+            last_modified: payload.last_modified,
+            source: payload.source.max(bootstrap_source),
+        })
+    };
+
+    get_cached_or_compute(&policy.cache_key(&[&gw]), policy, bonus).await
+}
+
+// The ?n= of /gameweek/:gw/top-performers, capped at TOP_PERFORMERS_MAX
+fn top_performers_n(query: Option<&str>) -> Result<usize, ProxyError> {
+    match query_param(query, "n").map(str::parse::<usize>) {
+        None => Ok(TOP_PERFORMERS_DEFAULT),
+        Some(Ok(n)) if n > 0 => Ok(n.min(TOP_PERFORMERS_MAX)),
+        Some(_) => Err(ProxyError::InvalidPathParam("n: expected a positive integer".to_string())),
+    }
+}
+
+// The `n` highest-scoring players of a gameweek, ties broken by player ID
+fn top_performers(live: &LiveEvent, bootstrap: &BootstrapStatic, n: usize) -> Vec<Value> {
+    let mut scored: Vec<_> = live.elements.iter().collect();
+    scored.sort_by_key(|element| (std::cmp::Reverse(element.stats.total_points.unwrap_or(0)), element.id));
+
+    scored
+        .into_iter()
+        .take(n)
+        .map(|element| {
+            let player = bootstrap.elements.iter().find(|player| player.id == element.id);
+            json!({
+                "player_id": element.id,
+                "web_name": player.map(|player| &player.web_name),
+                "team": player.map(|player| player.team),
+                "points": element.stats.total_points.unwrap_or(0),
+                "goals": element.stats.goals_scored.unwrap_or(0),
+                "assists": element.stats.assists.unwrap_or(0),
+                "bonus": element.stats.bonus.unwrap_or(0)
+            })
+        })
+        .collect()
+}
+
+async fn handle_top_performers(gw: &str, n: usize) -> Result<Payload, ProxyError> {
+    let gw = validate_gameweek(gw)?;
+    let policy = CachePolicy::TOP_PERFORMERS;
+    let top = async {
+        let event = gw.to_string();
+        let (payload, (bootstrap, bootstrap_source)) = tokio::try_join!(handle_live_event(&event, None), bootstrap_static())?;
+        let live: LiveEvent = serde_json::from_value(payload.data).map_err(ProxyError::JsonParseFailure)?;
+        Ok(FetchResult {
+            data: Value::Array(top_performers(&live, &bootstrap, n)),
+            last_modified: payload.last_modified,
+            source: payload.source.max(bootstrap_source),
+        })
+    };
+
+    get_cached_or_compute(&policy.cache_key(&[&gw, &n]), policy, top).await
+}
+
+// The ?threshold= of /gameweek/:gw/differentials, a percentage above 0 and at most 100
+fn differential_threshold(query: Option<&str>) -> Result<f64, ProxyError> {
+    match query_param(query, "threshold").map(str::parse::<f64>) {
+        None => Ok(DIFFERENTIAL_OWNERSHIP_DEFAULT),
+        Some(Ok(threshold)) if threshold > 0.0 && threshold <= 100.0 => Ok(threshold),
+        Some(_) => Err(ProxyError::InvalidPathParam("threshold: expected a percentage above 0 and at most 100".to_string())),
+    }
+}
+
+// Players selected by fewer than `threshold` percent of managers, highest
+// gameweek score first, ties broken by player ID
+fn differentials(live: &LiveEvent, bootstrap: &BootstrapStatic, threshold: f64) -> Vec<Value> {
+    let mut picks: Vec<_> = live
+        .elements
+        .iter()
+        .filter_map(|element| {
+            let player = bootstrap.elements.iter().find(|player| player.id == element.id)?;
+            let ownership = player.selected_by_percent.parse::<f64>().ok().filter(|ownership| *ownership < threshold)?;
+            Some((element, player, ownership))
+        })
+        .collect();
+    picks.sort_by_key(|(element, ..)| (std::cmp::Reverse(element.stats.total_points.unwrap_or(0)), element.id));
+
+    picks
+        .into_iter()
+        .map(|(element, player, ownership)| {
+            json!({
+                "player_id": element.id,
+                "web_name": player.web_name,
+                "team": player.team,
+                "selected_by_percent": ownership,
+                "points": element.stats.total_points.unwrap_or(0)
+            })
+        })
+        .collect()
+}
+
+async fn handle_differentials(gw: &str, ownership_threshold: f64) -> Result<Payload, ProxyError> {
+    let gw = validate_gameweek(gw)?;
+    let policy = CachePolicy::DIFFERENTIALS;
+    let picks = async {
+        let event = gw.to_string();
+        let (payload, (bootstrap, bootstrap_source)) = tokio::try_join!(handle_live_event(&event, None), bootstrap_static())?;
+        let live: LiveEvent = serde_json::from_value(payload.data).map_err(ProxyError::JsonParseFailure)?;
+        Ok(FetchResult {
+            data: Value::Array(differentials(&live, &bootstrap, ownership_threshold)),
+            last_modified: payload.last_modified,
+            source: payload.source.max(bootstrap_source),
+        })
+    };
+
+    get_cached_or_compute(&policy.cache_key(&[&gw, &ownership_threshold]), policy, picks).await
+}
+
+// FPL does not publish captain counts per player, so the most-owned players
+// stand in for the likely captains, with their live points doubled as the
+// captain's return. The event's `most_captained` player, once upstream knows
+// it, is flagged.
+fn captain_choices(live: &LiveEvent, bootstrap: &BootstrapStatic, gw: u32) -> Vec<Value> {
+    let most_captained = bootstrap
+        .events
+        .iter()
+        .find(|event| event.id == gw)
+        .and_then(|event| event.extra.get("most_captained"))
+        .and_then(Value::as_u64);
+
+    let mut owned: Vec<_> = bootstrap
+        .elements
+        .iter()
+        .filter_map(|player| Some((player, player.selected_by_percent.parse::<f64>().ok()?)))
+        .collect();
+    owned.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.id.cmp(&b.0.id)));
+
+    owned
+        .into_iter()
+        .take(CAPTAIN_CHOICES_COUNT)
+        .map(|(player, ownership)| {
+            let points = live
+                .elements
+                .iter()
+                .find(|element| element.id == player.id)
+                .and_then(|element| element.stats.total_points)
+                .unwrap_or(0);
+            json!({
+                "player_id": player.id,
+                "web_name": player.web_name,
+                "team": player.team,
+                "selected_by_percent": ownership,
+                "points": points,
+                "captain_points": points * 2,
+                "most_captained": most_captained == Some(u64::from(player.id))
+            })
+        })
+        .collect()
+}
+
+async fn handle_captain_choices(gw: &str) -> Result<Payload, ProxyError> {
+    let gw = validate_gameweek(gw)?;
+    let policy = CachePolicy::CAPTAIN_CHOICES;
+    let choices = async {
+        let event = gw.to_string();
+        let (payload, (bootstrap, bootstrap_source)) = tokio::try_join!(handle_live_event(&event, None), bootstrap_static())?;
+        let live: LiveEvent = serde_json::from_value(payload.data).map_err(ProxyError::JsonParseFailure)?;
+        Ok(FetchResult {
+            data: Value::Array(captain_choices(&live, &bootstrap, gw)),
+            last_modified: payload.last_modified,
+            source: payload.source.max(bootstrap_source),
+        })
+    };
+
+    get_cached_or_compute(&policy.cache_key(&[&gw]), policy, choices).await
+}
+
+async fn handle_picks(manager_id: &str, gw: &str, query: Option<&str>) -> Result<Payload, ProxyError> {
+    let manager_id = validate_numeric_id(manager_id)?;
+    let gw = validate_numeric_id(gw)?;
+    let endpoint = FplEndpoint::Picks { manager_id, gw };
+    let url = with_query(endpoint.primary_url(), query);
+    let policy = CachePolicy::PICKS;
+
+    get_cached_or_fetch(&with_query(policy.cache_key(&[&manager_id, &gw]), query), policy, &url, None, endpoint.local_backup()).await
+}
+
+// An array field of an upstream response, empty when upstream omits it
+fn array_field(data: &Value, field: &str) -> Value {
+    data.get(field)
+        .filter(|subs| subs.is_array())
+        .cloned()
+        .unwrap_or_else(|| json!([]))
+}
+
+// Derived from the picks response, so it shares the picks cache entry and TTL
+async fn handle_automatic_subs(manager_id: &str, gw: &str, query: Option<&str>) -> Result<Payload, ProxyError> {
+    let picks = handle_picks(manager_id, gw, query).await?;
+    let subs = array_field(&picks.data, "automatic_subs");
+
+    Ok(Payload {
+        etag: compute_etag(&subs),
+        data: subs,
+        last_modified: picks.last_modified,
+        source: picks.source,
+        cache_status: picks.cache_status,
+        headers: Vec::new(),
+    })
+}
+
+// Gameweek points of a squad and whether any scoring player lacked live data.
+// The captain's multiplier passes to the vice-captain if the captain did not play.
+// Chips are applied even when upstream multipliers do not reflect them yet.
+fn gameweek_points(picks: &EntryPicks, live: &LiveEvent) -> (i32, bool) {
+    let chip = picks.chip();
+    let played = |element| live.stats(element).and_then(|stats| stats.minutes).unwrap_or(0) > 0;
+    let scores = |pick: &&Pick| pick.multiplier > 0 || chip == Some(Chip::BenchBoost);
+
+    let captain = picks.picks.iter().find(|pick| pick.is_captain);
+    let captain_multiplier = match chip {
+        Some(Chip::TripleCaptain) => 3,
+        _ => captain.map_or(2, |captain| captain.multiplier.max(2)),
+    };
+    let vice_takes_over = captain.is_some_and(|captain| !played(captain.element))
+        && picks.picks.iter().filter(scores).any(|pick| pick.is_vice_captain && played(pick.element));
+
+    let mut total = 0;
+    let mut is_estimated = false;
+    for pick in picks.picks.iter().filter(scores) {
+        let multiplier = if (pick.is_captain && !vice_takes_over) || (pick.is_vice_captain && vice_takes_over) {
+            captain_multiplier
+        } else {
+            1
+        };
+        match live.stats(pick.element).and_then(|stats| stats.total_points) {
+            Some(points) => total += points * multiplier as i32,
+            None => is_estimated = true,
+        }
+    }
+    (total, is_estimated)
+}
+
+// Joins picks and live data, both served from their own cache entries
+async fn handle_manager_gw_points(manager_id: &str, gw: &str) -> Result<Payload, ProxyError> {
+    let manager_id = validate_numeric_id(manager_id)?;
+    let gw = validate_numeric_id(gw)?;
+    let policy = CachePolicy::GW_POINTS;
+    let points = async {
+        let (manager, event) = (manager_id.to_string(), gw.to_string());
+        let (picks, live) = tokio::try_join!(handle_picks(&manager, &event, None), handle_live_event(&event, None))?;
+        let source = picks.source.max(live.source);
+        let picks: EntryPicks = serde_json::from_value(picks.data).map_err(ProxyError::JsonParseFailure)?;
+        let live: LiveEvent = serde_json::from_value(live.data).map_err(ProxyError::JsonParseFailure)?;
+
+        let (total_points, is_estimated) = gameweek_points(&picks, &live);
+        Ok(FetchResult {
+            data: json!({
+                "manager_id": manager_id,
+                "gw": gw,
+                "total_points": total_points,
+                "is_estimated": is_estimated
+            }),
+            last_modified: None,
+            source,
+        })
+    };
+
+    get_cached_or_compute(&policy.cache_key(&[&manager_id, &gw]), policy, points).await
+}
+
+// (squad value, bank) in tenths of a million, from each pick's current price.
+// Picks whose player is missing from bootstrap-static count as 0.
+fn team_value(picks: &EntryPicks, bootstrap: &BootstrapStatic) -> (u32, u32) {
+    let squad_value = picks
+        .picks
+        .iter()
+        .filter_map(|pick| bootstrap.elements.iter().find(|player| player.id == pick.element))
+        .map(|player| player.now_cost)
+        .sum();
+    let bank = picks
+        .extra
+        .get("entry_history")
+        .and_then(|history| history.get("bank"))
+        .and_then(Value::as_u64)
+        .unwrap_or(0) as u32;
+    (squad_value, bank)
+}
+
+// Joins the cached picks and bootstrap-static, so it needs no cache entry of its own
+async fn handle_team_value(manager_id: &str, gw: &str) -> Result<Payload, ProxyError> {
+    let manager_id = validate_numeric_id(manager_id)?;
+    let gw = validate_numeric_id(gw)?;
+    let (manager, event) = (manager_id.to_string(), gw.to_string());
+
+    let (picks, (bootstrap, bootstrap_source)) = tokio::try_join!(handle_picks(&manager, &event, None), bootstrap_static())?;
+    let source = picks.source.max(bootstrap_source);
+    let picks: EntryPicks = serde_json::from_value(picks.data).map_err(ProxyError::JsonParseFailure)?;
+
+    let (squad_value, bank) = team_value(&picks, &bootstrap);
+    let data = json!({
+        "squad_value": squad_value,
+        "bank": bank,
+        "total_value": squad_value + bank
+    });
+    Ok(Payload { source, ..Payload::from(data) })
+}
+
+async fn handle_dream_team(gw: &str, query: Option<&str>) -> Result<Payload, ProxyError> {
+    let gw = validate_numeric_id(gw)?;
+    let endpoint = FplEndpoint::DreamTeam(gw);
+    let url = with_query(endpoint.primary_url(), query);
+    let policy = CachePolicy::DREAM_TEAM;
+
+    get_cached_or_fetch(&with_query(policy.cache_key(&[&gw]), query), policy, &url, None, endpoint.local_backup()).await
+}
+
+async fn handle_manager_info(id: &str, query: Option<&str>) -> Result<Payload, ProxyError> {
+    let endpoint = FplEndpoint::ManagerInfo(validate_numeric_id(id)?);
+    let url = with_query(endpoint.primary_url(), query);
+    fetch_with_fallback(&url, None, None).await.map(Payload::from)
+}
+
+async fn handle_manager_transfers(id: &str, query: Option<&str>) -> Result<Payload, ProxyError> {
+    let endpoint = FplEndpoint::ManagerTransfers(validate_numeric_id(id)?);
+    let url = with_query(endpoint.primary_url(), query);
+    fetch_with_fallback(&url, None, None).await.map(Payload::from)
+}
+
+// Transfers made for the latest gameweek that has any, in upstream order
+fn latest_transfers(transfers: &[Value]) -> Vec<Value> {
+    let Some(latest) = transfers.iter().filter_map(|transfer| transfer["event"].as_u64()).max() else {
+        return Vec::new();
+    };
+
+    transfers
+        .iter()
+        .filter(|transfer| transfer["event"].as_u64() == Some(latest))
+        .cloned()
+        .collect()
+}
+
+async fn handle_latest_transfers(id: &str) -> Result<Payload, ProxyError> {
+    let id = validate_numeric_id(id)?;
+    let policy = CachePolicy::LATEST_TRANSFERS;
+    let latest = async {
+        let transfers = handle_manager_transfers(&id.to_string(), None).await?;
+        let latest = transfers.data.as_array().map(|transfers| latest_transfers(transfers)).unwrap_or_default();
+        Ok(FetchResult {
+            data: Value::Array(latest),
+            last_modified: transfers.last_modified,
+            source: transfers.source,
+        })
+    };
+
+    get_cached_or_compute(&policy.cache_key(&[&id]), policy, latest).await
+}
+
+async fn handle_manager_history(id: &str, query: Option<&str>) -> Result<Payload, ProxyError> {
+    let endpoint = FplEndpoint::ManagerHistory(validate_numeric_id(id)?);
+    let url = with_query(endpoint.primary_url(), query);
+    fetch_with_fallback(&url, None, None).await.map(Payload::from)
+}
+
+// The chips array of a manager history, one {name, label, gameweek, played_at} per chip
+fn chip_history(history: &Value) -> Value {
+    let chips = history.get("chips").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+
+    chips
+        .iter()
+        .map(|chip| {
+            let name = chip.get("name").and_then(Value::as_str).unwrap_or_default();
+            json!({
+                "name": name,
+                "label": Chip::from_code(name).map(Chip::label),
+                "gameweek": chip.get("event"),
+                "played_at": chip.get("time")
+            })
+        })
+        .collect()
+}
+
+// Played chips never change, so the extract is cached even though the history is not
+async fn handle_chip_history(id: &str) -> Result<Payload, ProxyError> {
+    let id = validate_numeric_id(id)?;
+    let policy = CachePolicy::CHIP_HISTORY;
+    let chips = async {
+        let history = handle_manager_history(&id.to_string(), None).await?;
+        Ok(FetchResult {
+            data: chip_history(&history.data),
+            last_modified: history.last_modified,
+            source: history.source,
+        })
+    };
+
+    get_cached_or_compute(&policy.cache_key(&[&id]), policy, chips).await
+}
+
+// Aggregates over the `current` array of a manager history, one entry per
+// gameweek played. Ties for best or worst gameweek go to the earliest one.
+fn season_summary(history: &Value) -> Value {
+    let gameweeks = history.get("current").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+    let field = |gameweek: &Value, name| gameweek.get(name).and_then(Value::as_i64).unwrap_or(0);
+
+    let mut best: Option<&Value> = None;
+    let mut worst: Option<&Value> = None;
+    for gameweek in gameweeks {
+        if best.is_none_or(|best| field(gameweek, "points") > field(best, "points")) {
+            best = Some(gameweek);
+        }
+        if worst.is_none_or(|worst| field(gameweek, "points") < field(worst, "points")) {
+            worst = Some(gameweek);
+        }
+    }
+
+    let points: i64 = gameweeks.iter().map(|gameweek| field(gameweek, "points")).sum();
+    let average_points = (!gameweeks.is_empty()).then(|| points as f64 / gameweeks.len() as f64);
+    let overall_rank_history: Vec<Value> = gameweeks
+        .iter()
+        .map(|gameweek| json!({"gw": gameweek.get("event"), "overall_rank": gameweek.get("overall_rank")}))
+        .collect();
+
+    json!({
+        "gameweeks_played": gameweeks.len(),
+        "total_points": gameweeks.last().and_then(|gameweek| gameweek.get("total_points")),
+        "best_gw": best.and_then(|gameweek| gameweek.get("event")),
+        "best_gw_points": best.and_then(|gameweek| gameweek.get("points")),
+        "worst_gw": worst.and_then(|gameweek| gameweek.get("event")),
+        "worst_gw_points": worst.and_then(|gameweek| gameweek.get("points")),
+        "average_points": average_points,
+        "total_transfers": gameweeks.iter().map(|gameweek| field(gameweek, "event_transfers")).sum::<i64>(),
+        "total_transfer_cost": gameweeks.iter().map(|gameweek| field(gameweek, "event_transfers_cost")).sum::<i64>(),
+        "overall_rank_history": overall_rank_history
+    })
+}
+
+async fn handle_season_summary(id: &str) -> Result<Payload, ProxyError> {
+    let id = validate_numeric_id(id)?;
+    let policy = CachePolicy::SEASON_SUMMARY;
+    let summary = async {
+        let history = handle_manager_history(&id.to_string(), None).await?;
+        Ok(FetchResult {
+            data: season_summary(&history.data),
+            last_modified: history.last_modified,
+            source: history.source,
+        })
+    };
+
+    get_cached_or_compute(&policy.cache_key(&[&id]), policy, summary).await
+}
+
+async fn handle_my_team(id: &str, query: Option<&str>) -> Result<Payload, ProxyError> {
+    let endpoint = FplEndpoint::MyTeam(validate_numeric_id(id)?);
+    let url = with_query(endpoint.primary_url(), query);
+    fetch_with_fallback(&url, None, None).await.map(Payload::from)
+}
+
+async fn handle_manager_cup(id: &str, query: Option<&str>) -> Result<Payload, ProxyError> {
+    let id = validate_numeric_id(id)?;
+    let endpoint = FplEndpoint::ManagerCup(id);
+    let url = with_query(endpoint.primary_url(), query);
+    let policy = CachePolicy::MANAGER_CUP;
+
+    get_cached_or_fetch(&with_query(policy.cache_key(&[&id]), query), policy, &url, None, endpoint.local_backup()).await
+}
+
+// Heavily rate-limited upstream with no backup, so failures get an explicit message
+async fn handle_transfers(query: Option<&str>) -> Result<Payload, ProxyError> {
+    let endpoint = FplEndpoint::Transfers;
+    let url = with_query(endpoint.primary_url(), query);
+    let policy = CachePolicy::TRANSFERS;
+
+    get_cached_or_fetch(&with_query(policy.cache_key(&[]), query), policy, &url, None, None)
+        .await
+        .map_err(|e| match e {
+            ProxyError::UpstreamUnavailable | ProxyError::AllSourcesFailed => ProxyError::NoBackupAvailable("/transfers"),
+            other => other,
+        })
+}
+
+async fn handle_league_standings(league_id: &str, page: &str, query: Option<&str>) -> Result<Payload, ProxyError> {
+    let endpoint = FplEndpoint::LeagueStandings {
+        league_id: validate_numeric_id(league_id)?,
+        page: validate_numeric_id(page)?,
+    };
+    let url = with_query(endpoint.primary_url(), query);
+    fetch_with_fallback(&url, None, None).await.map(Payload::from)
+}
+
+// Managers who recently joined a classic league, without its standings
+async fn handle_league_new_entries(league_id: &str, page: &str) -> Result<Payload, ProxyError> {
+    let league_id = validate_numeric_id(league_id)?;
+    let page = validate_numeric_id(page)?;
+    let endpoint = FplEndpoint::LeagueNewEntries { league_id, page };
+    let policy = CachePolicy::LEAGUE_NEW_ENTRIES;
+
+    get_cached_or_fetch(&policy.cache_key(&[&league_id, &page]), policy, &endpoint.primary_url(), None, None).await
+}
+
+// Take the standings.results array out of a standings page
+fn take_standings_results(page: &mut Value) -> Vec<Value> {
+    match page.pointer_mut("/standings/results").map(Value::take) {
+        Some(Value::Array(results)) => results,
+        _ => Vec::new(),
+    }
+}
+
+fn has_next_page(page: &Value) -> bool {
+    page.pointer("/standings/has_next").and_then(Value::as_bool).unwrap_or(false)
+}
+
+// Follow has_next from page 1, merging every page's standings.results into the first page
+async fn fetch_all_standings_pages(page_url: impl Fn(u32) -> String) -> Result<FetchResult, ProxyError> {
+    fetch_standings_pages(page_url, usize::MAX).await
+}
+
+// Like fetch_all_standings_pages, but stops once `max_results` entries have been collected
+async fn fetch_standings_pages(page_url: impl Fn(u32) -> String, max_results: usize) -> Result<FetchResult, ProxyError> {
+    let first = fetch_with_fallback(&page_url(1), None, None).await?;
+    let mut merged = first.data;
+    if !merged.is_object() {
+        return Err(ProxyError::AllSourcesFailed);
+    }
+
+    let mut results = take_standings_results(&mut merged);
+    let mut has_next = has_next_page(&merged);
+    let mut page = 1;
+
+    while has_next && results.len() < max_results {
+        if page == LEAGUE_ALL_MAX_PAGES {
+            warn!("Stopped merging league standings at the {} page cap", LEAGUE_ALL_MAX_PAGES);
+            break;
+        }
+        page += 1;
+
+        let mut data = fetch_with_fallback(&page_url(page), None, None).await?.data;
+        results.extend(take_standings_results(&mut data));
+        has_next = has_next_page(&data);
+    }
+
+    // has_next stays true when the page cap or max_results stopped before the last page
+    merged["standings"]["results"] = Value::Array(results);
+    merged["standings"]["has_next"] = Value::Bool(has_next);
+
+    Ok(FetchResult {
+        data: merged,
+        last_modified: first.last_modified,
+        source: first.source,
+    })
+}
+
+async fn handle_league_standings_all(league_id: &str, query: Option<&str>) -> Result<Payload, ProxyError> {
+    let league_id = validate_numeric_id(league_id)?;
+    let policy = CachePolicy::LEAGUE_ALL;
+    let page_url = |page| with_query(FplEndpoint::LeagueStandings { league_id, page }.primary_url(), query);
+
+    get_cached_or_compute(&with_query(policy.cache_key(&[&league_id]), query), policy, fetch_all_standings_pages(page_url)).await
+}
+
+// Validate the N of /league/:id/top/:n
+fn validate_top_n(n: &str) -> Result<usize, ProxyError> {
+    match validate_numeric_id(n)? as usize {
+        n @ 1..=LEAGUE_TOP_MAX => Ok(n),
+        _ => Err(ProxyError::InvalidPathParam(format!("top N: expected 1 to {}", LEAGUE_TOP_MAX))),
+    }
+}
+
+async fn handle_league_top(league_id: &str, n: usize, query: Option<&str>) -> Result<Payload, ProxyError> {
+    let league_id = validate_numeric_id(league_id)?;
+    let policy = CachePolicy::LEAGUE_TOP;
+    let page_url = |page| with_query(FplEndpoint::LeagueStandings { league_id, page }.primary_url(), query);
+    let top = async {
+        let fetched = fetch_standings_pages(page_url, n).await?;
+        let mut results = match fetched.data.pointer("/standings/results") {
+            Some(Value::Array(results)) => results.clone(),
+            _ => Vec::new(),
+        };
+        results.truncate(n);
+        Ok(FetchResult {
+            data: Value::Array(results),
+            last_modified: fetched.last_modified,
+            source: fetched.source,
+        })
+    };
+
+    get_cached_or_compute(&with_query(policy.cache_key(&[&league_id, &n]), query), policy, top).await
+}
+
+// One of the classic leagues a manager has joined, from /entry/:id/
+fn classic_league(info: &Value, league_id: u32) -> Option<&Value> {
+    info.pointer("/leagues/classic")?
+        .as_array()?
+        .iter()
+        .find(|league| league.get("id").and_then(Value::as_u64) == Some(league_id.into()))
+}
+
+// The classic then H2H leagues of a manager's /entry/:id/ info, each tagged with its type
+fn manager_leagues(info: &Value) -> Vec<Value> {
+    ["classic", "h2h"]
+        .into_iter()
+        .flat_map(|league_type| {
+            let leagues = info.pointer(&format!("/leagues/{}", league_type)).and_then(Value::as_array);
+            leagues.into_iter().flatten().map(move |league| {
+                json!({
+                    "id": league.get("id"),
+                    "name": league.get("name"),
+                    "type": league_type,
+                    "entry_rank": league.get("entry_rank"),
+                    "entry_last_rank": league.get("entry_last_rank")
+                })
+            })
+        })
+        .collect()
+}
+
+async fn handle_manager_leagues(id: &str) -> Result<Payload, ProxyError> {
+    let id = validate_numeric_id(id)?;
+    let policy = CachePolicy::MINI_LEAGUES;
+    let leagues = async {
+        let info = handle_manager_info(&id.to_string(), None).await?;
+        Ok(FetchResult {
+            data: Value::Array(manager_leagues(&info.data)),
+            last_modified: info.last_modified,
+            source: info.source,
+        })
+    };
+
+    get_cached_or_compute(&policy.cache_key(&[&id]), policy, leagues).await
+}
+
+// (rank, total entries) of a manager in one of their classic leagues
+fn classic_league_rank(info: &Value, league_id: u32) -> Option<(u64, u64)> {
+    let league = classic_league(info, league_id)?;
+
+    // entry_rank is 0 or null until the league's first gameweek has been scored
+    let rank = league.get("entry_rank").and_then(Value::as_u64).filter(|rank| *rank > 0)?;
+    let total = league.get("rank_count").and_then(Value::as_u64).unwrap_or(rank);
+    Some((rank, total))
+}
+
+// The manager's row of a standings page
+fn find_standing(page: &Value, manager_id: u32) -> Option<&Value> {
+    page.pointer("/standings/results")?
+        .as_array()?
+        .iter()
+        .find(|result| result.get("entry").and_then(Value::as_u64) == Some(manager_id.into()))
+}
+
+// Where a manager with `points` sits relative to a standings page, whose rows
+// are ordered by total points, highest first: Less for an earlier page,
+// Greater for a later one and Equal when within the page's points range
+fn compare_page_points(page: &Value, points: i64) -> Option<std::cmp::Ordering> {
+    let results = page.pointer("/standings/results")?.as_array()?;
+    let highest = results.first()?.get("total")?.as_i64()?;
+    let lowest = results.last()?.get("total")?.as_i64()?;
+    Some(if points > highest {
+        std::cmp::Ordering::Less
+    } else if points < lowest {
+        std::cmp::Ordering::Greater
+    } else {
+        std::cmp::Ordering::Equal
+    })
+}
+
+// Pages within `radius` of `landing`, nearest first and the earlier of each
+// pair first, limited to 1..=page_count
+fn neighbouring_pages(landing: u32, page_count: u32, radius: u32) -> Vec<u32> {
+    (1..=radius)
+        .flat_map(|distance| [landing.checked_sub(distance), landing.checked_add(distance)])
+        .flatten()
+        .filter(|page| (1..=page_count).contains(page))
+        .collect()
+}
+
+// The rank response when the manager is on this standings page
+fn league_rank_result(fetched: &FetchResult, manager_id: u32, hint_rank: u64, total: u64) -> Option<FetchResult> {
+    let standing = find_standing(&fetched.data, manager_id)?;
+    Some(FetchResult {
+        data: json!({
+            "rank": standing.get("rank").and_then(Value::as_u64).unwrap_or(hint_rank),
+            "total": total,
+            "entry_name": standing.get("entry_name"),
+            "points": standing.get("total")
+        }),
+        last_modified: fetched.last_modified.clone(),
+        source: fetched.source,
+    })
+}
+
+// Binary search over the standings pages, bounded by the league's entry count
+// from the manager's entry. Pages are ordered by points, and in a league that
+// started in gameweek 1 the manager's league points are their overall points.
+// Other leagues, and searches that land next to the manager rather than on
+// them, fall back to checking the pages around the landing page in both
+// directions, since the entry's rank and points can lag the standings.
+async fn handle_manager_league_rank(league_id: &str, manager_id: &str) -> Result<Payload, ProxyError> {
+    let league_id = validate_numeric_id(league_id)?;
+    let manager_id = validate_numeric_id(manager_id)?;
+    let policy = CachePolicy::LEAGUE_RANK;
+    let rank = async {
+        let info = handle_manager_info(&manager_id.to_string(), None).await?;
+        let (hint_rank, total) = classic_league_rank(&info.data, league_id).ok_or(ProxyError::NotFound)?;
+        let page_count = total.div_ceil(LEAGUE_STANDINGS_PAGE_SIZE).clamp(1, u32::MAX.into()) as u32;
+        let fetch_page = |page: u32| async move {
+            fetch_with_fallback(&FplEndpoint::LeagueStandings { league_id, page }.primary_url(), None, None).await
+        };
+
+        // Page 1 tells whether the league started in gameweek 1
+        let first = fetch_page(1).await?;
+        if let Some(result) = league_rank_result(&first, manager_id, hint_rank, total) {
+            return Ok(result);
+        }
+        let mut visited = HashSet::from([1]);
+
+        let started_in_gw1 = first.data.pointer("/league/start_event").and_then(Value::as_u64).is_none_or(|event| event <= 1);
+        let points = info.data.get("summary_overall_points").and_then(Value::as_i64).filter(|_| started_in_gw1);
+        let mut landing = (((hint_rank - 1) / LEAGUE_STANDINGS_PAGE_SIZE + 1) as u32).min(page_count);
+
+        if let Some(points) = points {
+            let (mut low, mut high) = (2, page_count);
+            while low <= high {
+                let page = low + (high - low) / 2;
+                let fetched = fetch_page(page).await?;
+                visited.insert(page);
+                if let Some(result) = league_rank_result(&fetched, manager_id, hint_rank, total) {
+                    return Ok(result);
+                }
+
+                landing = page;
+                match compare_page_points(&fetched.data, points) {
+                    Some(std::cmp::Ordering::Less) => high = page - 1,
+                    Some(std::cmp::Ordering::Greater) => low = page + 1,
+                    _ => break,
+                }
+            }
+        }
+
+        for page in std::iter::once(landing).chain(neighbouring_pages(landing, page_count, LEAGUE_RANK_SCAN_PAGES)) {
+            if !visited.insert(page) {
+                continue;
+            }
+            let fetched = fetch_page(page).await?;
+            if let Some(result) = league_rank_result(&fetched, manager_id, hint_rank, total) {
+                return Ok(result);
+            }
+        }
+        Err(ProxyError::NotFound)
+    };
+
+    get_cached_or_compute(&policy.cache_key(&[&league_id, &manager_id]), policy, rank).await
+}
+
+// Head-to-head record of two managers over the gameweeks both played, each
+// gameweek scored as points minus transfer costs like FPL's H2H leagues
+fn simulated_h2h(history_a: &Value, history_b: &Value) -> Value {
+    fn net_points(history: &Value) -> Vec<(u64, i64)> {
+        let gameweeks = history.get("current").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+        gameweeks
+            .iter()
+            .filter_map(|gameweek| {
+                let field = |name| gameweek.get(name).and_then(Value::as_i64).unwrap_or(0);
+                Some((gameweek.get("event")?.as_u64()?, field("points") - field("event_transfers_cost")))
+            })
+            .collect()
+    }
+
+    let scores_b = net_points(history_b);
+    let (mut wins_a, mut wins_b, mut draws) = (0, 0, 0);
+    let mut history = Vec::new();
+    for (gw, score_a) in net_points(history_a) {
+        let Some(&(_, score_b)) = scores_b.iter().find(|(event, _)| *event == gw) else {
+            continue;
+        };
+        let winner = match score_a.cmp(&score_b) {
+            std::cmp::Ordering::Greater => {
+                wins_a += 1;
+                "a"
+            }
+            std::cmp::Ordering::Less => {
+                wins_b += 1;
+                "b"
+            }
+            std::cmp::Ordering::Equal => {
+                draws += 1;
+                "draw"
+            }
+        };
+        history.push(json!({"gw": gw, "score_a": score_a, "score_b": score_b, "winner": winner}));
+    }
+
+    json!({"wins_a": wins_a, "wins_b": wins_b, "draws": draws, "history": history})
+}
+
+// Simulated H2H between two members of a classic league, which has no real H2H
+async fn handle_simulated_h2h(league_id: &str, entry_a: &str, entry_b: &str) -> Result<Payload, ProxyError> {
+    let league_id = validate_numeric_id(league_id)?;
+    let entry_a = validate_numeric_id(entry_a)?;
+    let entry_b = validate_numeric_id(entry_b)?;
+    let policy = CachePolicy::SIMULATED_H2H;
+    let h2h = async {
+        let (a, b) = (entry_a.to_string(), entry_b.to_string());
+        let (info_a, info_b, history_a, history_b) = tokio::try_join!(
+            handle_manager_info(&a, None),
+            handle_manager_info(&b, None),
+            handle_manager_history(&a, None),
+            handle_manager_history(&b, None)
+        )?;
+        if classic_league(&info_a.data, league_id).is_none() || classic_league(&info_b.data, league_id).is_none() {
+            return Err(ProxyError::NotFound);
+        }
+
+        Ok(FetchResult {
+            data: simulated_h2h(&history_a.data, &history_b.data),
+            last_modified: None,
+            source: [info_a.source, info_b.source, history_a.source, history_b.source].into_iter().max().unwrap_or_default(),
+        })
+    };
+
+    get_cached_or_compute(&policy.cache_key(&[&league_id, &entry_a, &entry_b]), policy, h2h).await
+}
+
+async fn handle_league_standings_by_phase(league_id: &str, phase: &str, query: Option<&str>) -> Result<Payload, ProxyError> {
+    let endpoint = FplEndpoint::LeagueStandingsByPhase {
+        league_id: validate_numeric_id(league_id)?,
+        phase: validate_numeric_id(phase)?,
+    };
+    let url = with_query(endpoint.primary_url(), query);
+    fetch_with_fallback(&url, None, None).await.map(Payload::from)
+}
+
+async fn handle_h2h_league_standings(league_id: &str, page: &str, query: Option<&str>) -> Result<Payload, ProxyError> {
+    let league_id = validate_numeric_id(league_id)?;
+    let page = validate_numeric_id(page)?;
+    let endpoint = FplEndpoint::H2hStandings { league_id, page };
+    let url = with_query(endpoint.primary_url(), query);
+    let policy = CachePolicy::H2H_STANDINGS;
+
+    get_cached_or_fetch(&with_query(policy.cache_key(&[&league_id, &page]), query), policy, &url, None, endpoint.local_backup()).await
+}
+
+async fn handle_h2h_matches(league_id: &str, page: &str) -> Result<Payload, ProxyError> {
+    let league_id = validate_numeric_id(league_id)?;
+    let page = validate_numeric_id(page)?;
+    let endpoint = FplEndpoint::H2hMatches { league_id, page };
+    let policy = CachePolicy::H2H_MATCHES;
+
+    get_cached_or_fetch(&policy.cache_key(&[&league_id, &page]), policy, &endpoint.primary_url(), None, endpoint.local_backup()).await
+}
+
+// Client IP as reported by Vercel's proxy headers
+fn client_ip(request: &Request) -> String {
+    let headers = request.headers();
+    headers
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .or_else(|| headers.get("x-real-ip").and_then(|value| value.to_str().ok()))
+        .map(|ip| ip.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+// Gzip the body when the client advertises support for it
+pub(crate) fn compress_if_accepted(req_headers: &HeaderMap, body: Bytes) -> (Bytes, Option<&'static str>) {
+    let accepts_gzip = req_headers
+        .get_all("accept-encoding")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|coding| {
+            let mut parts = coding.split(';').map(str::trim);
+            let name = parts.next().unwrap_or_default();
+            let disabled = parts.any(|param| param.replace(' ', "") == "q=0");
+            name.eq_ignore_ascii_case("gzip") && !disabled
+        });
+
+    if !accepts_gzip || body.len() < MIN_COMPRESS_BYTES {
+        return (body, None);
+    }
+
+    let mut encoder = GzEncoder::new(Vec::with_capacity(body.len() / 4), Compression::default());
+    match encoder.write_all(&body).and_then(|_| encoder.finish()) {
+        Ok(compressed) => (Bytes::from(compressed), Some("gzip")),
+        Err(e) => {
+            error!("Failed to gzip response body: {}", e);
+            (body, None)
+        }
+    }
+}
+
+/// Encoding of a successful response body, as negotiated from `Accept`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BodyFormat {
+    Json,
+    // JSON labelled text/json, for clients that accept only that
+    TextJson,
+    Msgpack,
+}
+
+// (media range, q-value) pairs listed in Accept, lowercased; q defaults to 1
+fn accepted_media_types(req_headers: &HeaderMap) -> Vec<(String, f32)> {
+    req_headers
+        .get_all("accept")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|media_range| {
+            let mut parts = media_range.split(';').map(str::trim);
+            let media_type = parts.next().unwrap_or_default().to_ascii_lowercase();
+            let quality = parts
+                .find_map(|param| param.replace(' ', "").strip_prefix("q=").map(|q| q.parse::<f32>().unwrap_or(0.0)))
+                .unwrap_or(1.0);
+            (!media_type.is_empty()).then_some((media_type, quality))
+        })
+        .collect()
+}
+
+// q-value Accept gives `media_type`, taken from the most specific range that
+// matches it (type/subtype, then type/*, then */*), or None when none does
+fn media_type_quality(accepted: &[(String, f32)], media_type: &str) -> Option<f32> {
+    let main_type = media_type.split('/').next().unwrap_or_default();
+    let ranges = [media_type.to_string(), format!("{}/*", main_type), "*/*".to_string()];
+    ranges
+        .iter()
+        .find_map(|range| accepted.iter().filter(|(accepted, _)| accepted == range).map(|(_, q)| *q).reduce(f32::max))
+}
+
+// The highest-weighted format Accept allows; ties go to application/json, then
+// text/json, so MessagePack is only sent when it is strictly preferred. A q=0
+// range refuses the type, and no Accept header at all means anything goes.
+fn negotiate_format(req_headers: &HeaderMap) -> Result<BodyFormat, ProxyError> {
+    if !req_headers.contains_key("accept") {
+        return Ok(BodyFormat::Json);
+    }
+
+    let accepted = accepted_media_types(req_headers);
+    let mut best: Option<(BodyFormat, f32)> = None;
+    for (format, media_type) in [
+        (BodyFormat::Json, "application/json"),
+        (BodyFormat::TextJson, "text/json"),
+        (BodyFormat::Msgpack, "application/msgpack"),
+    ] {
+        let quality = media_type_quality(&accepted, media_type).unwrap_or(0.0);
+        if quality > 0.0 && best.is_none_or(|(_, best_quality)| quality > best_quality) {
+            best = Some((format, quality));
+        }
+    }
+    best.map(|(format, _)| format).ok_or(ProxyError::NotAcceptable)
+}
+
+// Content type and body of a successful response: a JSONP call when a
+// callback was given, else the negotiated format
+fn encode_body(data: &Value, callback: Option<&str>, format: BodyFormat, pretty: bool) -> Result<(&'static str, Vec<u8>), ProxyError> {
+    if let Some(callback) = callback {
+        let json = serde_json::to_string(data).map_err(ProxyError::JsonParseFailure)?;
+        return Ok(("application/javascript", format!("{}({});", callback, json).into_bytes()));
+    }
+    let content_type = match format {
+        BodyFormat::Msgpack => {
+            let body = rmp_serde::to_vec_named(data).map_err(ProxyError::MsgpackEncodeFailure)?;
+            return Ok(("application/msgpack", body));
+        }
+        BodyFormat::Json => "application/json",
+        BodyFormat::TextJson => "text/json",
+    };
+    if pretty {
+        let mut body = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(PRETTY_JSON_INDENT);
+        data.serialize(&mut serde_json::Serializer::with_formatter(&mut body, formatter))
+            .map_err(ProxyError::JsonParseFailure)?;
+        return Ok((content_type, body));
+    }
+    let body = serde_json::to_vec(data).map_err(ProxyError::JsonParseFailure)?;
+    Ok((content_type, body))
+}
+
+// Value of access-control-allow-origin for a request, or None when it must be omitted
+fn cors_allow_origin<'a>(allowed: Option<&[String]>, origin: Option<&'a str>) -> Option<&'a str> {
+    match allowed {
+        None => Some("*"),
+        Some(allowed) => origin.filter(|origin| allowed.iter().any(|allowed| allowed == origin)),
+    }
+}
+
+// Add the allow-origin header, reflecting the request origin when an allow-list is configured
+fn with_cors_origin(builder: Builder, origin: Option<&str>) -> Builder {
+    let allowed = allowed_origins();
+    let builder = match cors_allow_origin(allowed, origin) {
+        Some(allow_origin) => builder.header("access-control-allow-origin", allow_origin),
+        None => builder,
+    };
+
+    // A reflected origin makes the response vary with the Origin header
+    if allowed.is_some() {
+        builder.header("vary", "Origin")
+    } else {
+        builder
+    }
+}
+
+fn request_origin(request: &Request) -> Option<&str> {
+    request.headers().get("origin").and_then(|value| value.to_str().ok())
+}
+
+fn metrics_response(origin: Option<&str>) -> Result<Response<Body>, Error> {
+    let text = metrics::render()?;
+
+    let builder = Response::builder()
+        .status(200)
+        .header("content-type", "text/plain; version=0.0.4")
+        .header("content-length", text.len())
+        .header("cache-control", "no-store");
+    with_cors_origin(builder, origin).body(Body::from(text)).map_err(Error::from)
+}
+
+fn ndjson_response(body: String, origin: Option<&str>) -> Result<Response<Body>, Error> {
+    let builder = Response::builder()
+        .status(200)
+        .header("content-type", "application/x-ndjson")
+        .header("content-length", body.len())
+        .header("cache-control", "no-store");
+    with_cors_origin(builder, origin).body(Body::from(body)).map_err(Error::from)
+}
+
+fn robots_response() -> Result<Response<Body>, Error> {
+    Response::builder()
+        .status(200)
+        .header("content-type", "text/plain")
+        .header("content-length", ROBOTS_TXT.len())
+        .header("cache-control", "public, max-age=86400") // 24 hours
+        .body(Body::from(ROBOTS_TXT))
+        .map_err(Error::from)
+}
+
+fn favicon_response() -> Result<Response<Body>, Error> {
+    Response::builder()
+        .status(200)
+        .header("content-type", "image/x-icon")
+        .header("content-length", FAVICON_ICO.len())
+        .header("cache-control", "public, max-age=31536000") // 1 year
+        .body(Body::from(FAVICON_ICO.to_vec()))
+        .map_err(Error::from)
+}
+
+fn error_response(proxy_error: &ProxyError, origin: Option<&str>) -> Result<Response<Body>, Error> {
+    error!("Request error: {}", proxy_error);
+
+    let mut error_body = json!({
+        "error": proxy_error.to_string(),
+        "timestamp": chrono::Utc::now().to_rfc3339()
+    });
+    // Point clients that misremembered a path at the ones that exist
+    if let ProxyError::UnknownRoute = proxy_error {
+        error_body["valid_routes"] = json!(router().patterns());
+    }
+    let error_body = error_body.to_string();
+
+    let builder = Response::builder()
+        .status(proxy_error.status_code())
+        .header("content-type", "application/json")
+        .header("content-length", error_body.len());
+    let mut builder = with_cors_origin(builder, origin)
+        .header("access-control-allow-methods", "GET, POST, PUT, DELETE, OPTIONS")
+        .header("access-control-allow-headers", "Content-Type, Authorization");
+
+    if let ProxyError::RateLimited { retry_after_secs } = proxy_error {
+        builder = builder.header("retry-after", retry_after_secs.to_string());
+    }
+
+    builder.body(Body::from(error_body)).map_err(Error::from)
+}
+
+// Install the global subscriber once per process, at start-up rather than per
+// request; JSON lines suit Vercel's log drain
+fn init_tracing() {
+    TRACING.get_or_init(|| {
+        tracing_subscriber::fmt()
+            .json()
+            .with_env_filter("info")
+            .try_init()
+            .ok();
+    });
+}
+
+// Pre-fetch the most expensive endpoint so the first requests after a cold start hit the cache
+pub(crate) async fn warm_cache() {
+    match handle_bootstrap_static(None).await {
+        Ok(_) => info!("Cache warmed with bootstrap-static"),
+        Err(e) => warn!("Cache warm-up failed: {}", e),
+    }
+}
+
+pub(crate) async fn handler(request: Request) -> Result<Response<Body>, Error> {
+    let started = Instant::now();
+    let path = request.uri().path().to_string();
+    let method = request.method().clone();
+    let request_id = request_id(request.headers());
+    let vercel_id = request
+        .headers()
+        .get("x-vercel-id")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+
+    // Every log line emitted while handling the request carries these fields
+    let span = info_span!(
+        "request",
+        request_id = %request_id,
+        vercel_id = %vercel_id,
+        path = %path,
+        method = %method,
+        route = field::Empty,
+        cache_hit = field::Empty,
+    );
+
+    let mut response = respond(request).instrument(span.clone()).await;
+
+    // Echo the ID so clients can quote it when reporting a problem
+    if let (Ok(response), Ok(value)) = (response.as_mut(), HeaderValue::from_str(&request_id)) {
+        response.headers_mut().insert("x-request-id", value);
+    }
+    if let Ok(response) = response.as_mut() {
+        response.headers_mut().insert("x-proxy-version", HeaderValue::from_static(PROXY_VERSION));
+    }
+
+    let status = response.as_ref().map_or(500, |response| response.status().as_u16());
+    let cache_status = response
+        .as_ref()
+        .ok()
+        .and_then(|response| response.headers().get("x-cache-status"))
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("NONE")
+        .to_string();
+
+    // The one log line per request, so Vercel log queries can filter on any of these
+    span.in_scope(|| {
+        info!(
+            method = %method,
+            path = %path,
+            status,
+            duration_ms = started.elapsed().as_millis() as u64,
+            cache_status = %cache_status,
+            "Request completed"
+        )
+    });
+    metrics::record_request(metrics_route(&path), status);
+
+    response
+}
+
+// The client's X-Request-ID when it is usable, otherwise a fresh UUID v4
+fn request_id(headers: &HeaderMap) -> String {
+    headers
+        .get("x-request-id")
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN)
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string())
+}
+
+// Every JSON endpoint; specific patterns come before the catch-alls that
+// reject malformed paths under the same prefix
+fn build_router() -> Router {
+    let mut router = Router::new();
+
+    router.add_route("/health", |_| {
+        Box::pin(async {
+            Ok(Payload::from(json!({
+                "status": "OK",
+                "service": "Fantasy PL Vercel Proxy (Rust)",
+                "timestamp": chrono::Utc::now().to_rfc3339()
+            })))
+        })
+    });
+    router.add_route("/status", |_| Box::pin(handle_status()));
+    router.add_route("/openapi.json", |_| Box::pin(async { handle_openapi() }));
+    router.add_route("/cache/status", |ctx| {
+        Box::pin(async move {
+            require_admin(ctx.request.headers())?;
+            handle_cache_status().await
+        })
+    });
+    router.add_route("/cache/:key", |ctx| {
+        Box::pin(async move {
+            if ctx.request.method() != Method::DELETE {
+                return Err(ProxyError::UnknownRoute);
+            }
+            require_admin(ctx.request.headers())?;
+            handle_cache_evict(ctx.param("key")).await
+        })
+    });
+    router.add_route("/bootstrap-static", |ctx| Box::pin(async move { handle_bootstrap_static(ctx.query()).await }));
+    router.add_route("/teams", |_| Box::pin(handle_teams()));
+    router.add_route("/team/:team_id/players", |ctx| {
+        Box::pin(async move { handle_team_players(ctx.param("team_id")).await })
+    });
+    router.add_route("/team/*", invalid_path("team path"));
+    router.add_route("/events", |_| Box::pin(handle_events()));
+    router.add_route("/events/current", |_| Box::pin(handle_flagged_event(|event| event.is_current)));
+    router.add_route("/events/next", |_| Box::pin(handle_flagged_event(|event| event.is_next)));
+    router.add_route("/element-types", |_| Box::pin(handle_element_types()));
+    router.add_route("/gameweek/current", |_| Box::pin(handle_current_gameweek()));
+    router.add_route("/gameweek/:gw/bonus", |ctx| Box::pin(async move { handle_gw_bonus(ctx.param("gw")).await }));
+    router.add_route("/gameweek/:gw/top-performers", |ctx| {
+        Box::pin(async move {
+            let n = top_performers_n(ctx.query())?;
+            handle_top_performers(ctx.param("gw"), n).await
+        })
+    });
+    router.add_route("/gameweek/:gw/differentials", |ctx| {
+        Box::pin(async move {
+            let threshold = differential_threshold(ctx.query())?;
+            handle_differentials(ctx.param("gw"), threshold).await
+        })
+    });
+    router.add_route("/gameweek/:gw/captain-choices", |ctx| {
+        Box::pin(async move { handle_captain_choices(ctx.param("gw")).await })
+    });
+    router.add_route("/gameweek/:gw/results", |ctx| Box::pin(async move { handle_gw_results(ctx.param("gw")).await }));
+    router.add_route("/gameweek/:gw/transfers-in-out", |ctx| {
+        Box::pin(async move { handle_gw_transfers(ctx.param("gw")).await })
+    });
+    router.add_route("/gameweek/*", invalid_path("gameweek path"));
+    router.add_route("/transfers", |ctx| Box::pin(async move { handle_transfers(ctx.query()).await }));
+    router.add_route("/season/history", |_| Box::pin(handle_season_history()));
+
+    router.add_route("/fixtures", |ctx| {
+        Box::pin(async move { paginated(ctx.path(), ctx.raw_query(), handle_fixtures(ctx.query())).await })
+    });
+    router.add_route("/fixtures/typed", |ctx| {
+        Box::pin(async move { paginated(ctx.path(), ctx.raw_query(), handle_fixtures_typed(ctx.query())).await })
+    });
+    router.add_route("/fixtures/upcoming", |ctx| {
+        Box::pin(async move { paginated(ctx.path(), ctx.raw_query(), handle_upcoming_fixtures()).await })
+    });
+    router.add_route("/fixtures/gameweek/:gw", |ctx| {
+        Box::pin(async move {
+            let fixtures = handle_fixtures_by_gameweek(ctx.param("gw"), ctx.query());
+            paginated(ctx.path(), ctx.raw_query(), fixtures).await
+        })
+    });
+    router.add_route("/fixtures/gameweek/*", invalid_path("gameweek"));
+
+    router.add_route("/element-summary/bulk", |ctx| {
+        Box::pin(async move {
+            require_enabled(feature_flags().bulk_endpoint)?;
+            let ids = parse_bulk_ids(ctx.request.method(), ctx.request.body(), ctx.raw_query())?;
+            handle_element_summary_bulk(ids).await
+        })
+    });
+    router.add_route("/element-summary/:id/fixtures", |ctx| {
+        Box::pin(async move { handle_player_fixtures(ctx.param("id")).await })
+    });
+    router.add_route("/element-summary/:id", |ctx| {
+        Box::pin(async move { handle_element_summary(ctx.param("id"), ctx.query()).await })
+    });
+    router.add_route("/element-summary/*", invalid_path("element ID"));
+
+    router.add_route("/live-event/:gw", |ctx| Box::pin(async move { handle_live_event(ctx.param("gw"), ctx.query()).await }));
+    router.add_route("/live-event/*", invalid_path("gameweek"));
+    // Alias mirroring the FPL API's own /event/:gw/live/ path
+    router.add_route("/event/:gw/live", |ctx| Box::pin(async move { handle_live_event(ctx.param("gw"), ctx.query()).await }));
+    router.add_route("/event/:gw/live/", |ctx| Box::pin(async move { handle_live_event(ctx.param("gw"), ctx.query()).await }));
+
+    router.add_route("/players", |ctx| {
+        Box::pin(async move {
+            let query = ctx.query();
+            handle_players(
+                u32_query_param(query, "min_price")?,
+                u32_query_param(query, "max_price")?,
+                u32_query_param(query, "position")?,
+                u32_query_param(query, "team")?,
+            )
+            .await
+        })
+    });
+    router.add_route("/player/search", |ctx| {
+        Box::pin(async move {
+            let search = query_param(ctx.query(), "q").map(decode_query_value).unwrap_or_default();
+            handle_player_search(&search).await
+        })
+    });
+    router.add_route("/player/:id/history", |ctx| {
+        Box::pin(async move { handle_player_history(ctx.param("id"), ctx.query()).await })
+    });
+    router.add_route("/player/:id/upcoming-fixtures", |ctx| {
+        Box::pin(async move { handle_player_upcoming_fixtures(ctx.param("id")).await })
+    });
+    router.add_route("/player/:id/stats", |ctx| Box::pin(async move { handle_player_stats(ctx.param("id")).await }));
+    router.add_route("/player/:id/form-trend", |ctx| Box::pin(async move { handle_form_trend(ctx.param("id")).await }));
+    router.add_route("/player/:id/ownership-trend", |ctx| {
+        Box::pin(async move { handle_ownership_trend(ctx.param("id")).await })
+    });
+    router.add_route("/player/:id/price-change-history", |ctx| {
+        Box::pin(async move { handle_price_history(ctx.param("id")).await })
+    });
+    router.add_route("/player/:id/:endpoint", invalid_path("player endpoint"));
+    router.add_route("/player/*", invalid_path("player path"));
+
+    router.add_route("/dream-team/:gw", |ctx| Box::pin(async move { handle_dream_team(ctx.param("gw"), ctx.query()).await }));
+    router.add_route("/dream-team/*", invalid_path("gameweek"));
+
+    router.add_route("/picks/:manager_id/:gw", |ctx| {
+        Box::pin(async move { handle_picks(ctx.param("manager_id"), ctx.param("gw"), ctx.query()).await })
+    });
+    router.add_route("/picks/*", invalid_path("picks path"));
+
+    router.add_route("/manager/:id", |ctx| Box::pin(async move { handle_manager_info(ctx.param("id"), ctx.query()).await }));
+    router.add_route("/manager/:id/transfers", |ctx| {
+        Box::pin(async move { handle_manager_transfers(ctx.param("id"), ctx.query()).await })
+    });
+    router.add_route("/manager/:id/transfers/latest", |ctx| {
+        Box::pin(async move { handle_latest_transfers(ctx.param("id")).await })
+    });
+    router.add_route("/manager/:id/history", |ctx| {
+        Box::pin(async move { handle_manager_history(ctx.param("id"), ctx.query()).await })
+    });
+    router.add_route("/manager/:id/squad", |ctx| Box::pin(async move { handle_my_team(ctx.param("id"), ctx.query()).await }));
+    router.add_route("/manager/:id/cup", |ctx| Box::pin(async move { handle_manager_cup(ctx.param("id"), ctx.query()).await }));
+    router.add_route("/manager/:id/event/:gw/automatic-subs", |ctx| {
+        Box::pin(async move { handle_automatic_subs(ctx.param("id"), ctx.param("gw"), ctx.query()).await })
+    });
+    router.add_route("/manager/:id/points/:gw", |ctx| {
+        Box::pin(async move { handle_manager_gw_points(ctx.param("id"), ctx.param("gw")).await })
+    });
+    router.add_route("/manager/:id/season-summary", |ctx| Box::pin(async move { handle_season_summary(ctx.param("id")).await }));
+    router.add_route("/manager/:id/mini-leagues", |ctx| Box::pin(async move { handle_manager_leagues(ctx.param("id")).await }));
+    router.add_route("/manager/:id/team-value/:gw", |ctx| {
+        Box::pin(async move { handle_team_value(ctx.param("id"), ctx.param("gw")).await })
+    });
+    router.add_route("/manager/:id/:endpoint", invalid_path("manager endpoint"));
+    router.add_route("/manager/*", invalid_path("manager path"));
+
+    router.add_route("/chip-history/:manager_id", |ctx| Box::pin(async move { handle_chip_history(ctx.param("manager_id")).await }));
+    router.add_route("/chip-history/*", invalid_path("chip history path"));
+
+    router.add_route("/league/:league_id/:page", |ctx| {
+        Box::pin(async move { handle_league_standings(ctx.param("league_id"), ctx.param("page"), ctx.query()).await })
+    });
+    router.add_route("/league/mon/:league_id/:phase", |ctx| {
+        Box::pin(async move {
+            handle_league_standings_by_phase(ctx.param("league_id"), ctx.param("phase"), ctx.query()).await
+        })
+    });
+    router.add_route("/league/:league_id/standings/all", |ctx| {
+        Box::pin(async move {
+            require_enabled(feature_flags().league_all_endpoint)?;
+            handle_league_standings_all(ctx.param("league_id"), ctx.query()).await
+        })
+    });
+    router.add_route("/league/:league_id/top/:n", |ctx| {
+        Box::pin(async move {
+            let n = validate_top_n(ctx.param("n"))?;
+            handle_league_top(ctx.param("league_id"), n, ctx.query()).await
+        })
+    });
+    router.add_route("/league/:league_id/rank/:manager_id", |ctx| {
+        Box::pin(async move { handle_manager_league_rank(ctx.param("league_id"), ctx.param("manager_id")).await })
+    });
+    router.add_route("/league/*", invalid_path("league path"));
+
+    router.add_route("/leagues-classic/:league_id/new-entries/:page", |ctx| {
+        Box::pin(async move { handle_league_new_entries(ctx.param("league_id"), ctx.param("page")).await })
+    });
+    router.add_route("/leagues-classic/:league_id/h2h/:entry_a/:entry_b", |ctx| {
+        Box::pin(async move {
+            handle_simulated_h2h(ctx.param("league_id"), ctx.param("entry_a"), ctx.param("entry_b")).await
+        })
+    });
+    router.add_route("/leagues-classic/*", invalid_path("league path"));
+
+    router.add_route("/leagues-h2h/:league_id/:page", |ctx| {
+        Box::pin(async move {
+            require_enabled(feature_flags().h2h_endpoint)?;
+            handle_h2h_league_standings(ctx.param("league_id"), ctx.param("page"), ctx.query()).await
+        })
+    });
+    router.add_route("/leagues-h2h/*", |_| {
+        Box::pin(async {
+            require_enabled(feature_flags().h2h_endpoint)?;
+            Err(ProxyError::InvalidPathParam("H2H league path".to_string()))
+        })
+    });
+
+    router.add_route("/h2h-league/:id/matches", |ctx| {
+        Box::pin(async move {
+            require_enabled(feature_flags().h2h_endpoint)?;
+            handle_h2h_matches(ctx.param("id"), "1").await
+        })
+    });
+    router.add_route("/h2h-league/:id/matches/:page", |ctx| {
+        Box::pin(async move {
+            require_enabled(feature_flags().h2h_endpoint)?;
+            handle_h2h_matches(ctx.param("id"), ctx.param("page")).await
+        })
+    });
+    router.add_route("/h2h-league/*", |_| {
+        Box::pin(async {
+            require_enabled(feature_flags().h2h_endpoint)?;
+            Err(ProxyError::InvalidPathParam("H2H league path".to_string()))
+        })
+    });
+
+    router
+}
+
+// Catch-all handler rejecting a malformed path under a known prefix
+fn invalid_path(what: &'static str) -> impl for<'a> Fn(RouteContext<'a>) -> BoxedFuture<'a, Result<Payload, ProxyError>> {
+    move |_| Box::pin(async move { Err(ProxyError::InvalidPathParam(what.to_string())) })
+}
+
+// Paths respond answers before the router, so they have no route pattern
+const UNROUTED_PATHS: &[&str] = &["/metrics", "/robots.txt", "/favicon.ico", "/element-summary/ndjson"];
+
+// Metrics label for `path`: its route pattern, or "unmatched" for paths no
+// route knows, so probes for arbitrary paths cannot add series
+fn metrics_route(path: &str) -> &str {
+    match UNROUTED_PATHS.iter().find(|unrouted| **unrouted == path) {
+        Some(unrouted) => unrouted,
+        None => router().find(path).unwrap_or("unmatched"),
+    }
+}
+
+fn router() -> &'static Router {
+    ROUTER.get_or_init(build_router)
+}
+
+async fn respond(request: Request) -> Result<Response<Body>, Error> {
+    let path = request.uri().path();
+    let if_none_match = request
+        .headers()
+        .get("if-none-match")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let if_modified_since = request
+        .headers()
+        .get("if-modified-since")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let origin = request_origin(&request);
+    // Held until the response is built, so shutdown waits for this request's cache writes
+    let Some(_in_flight) = SHUTDOWN.begin().await else {
+        return error_response(&ProxyError::ShuttingDown, origin);
+    };
+    // Answer CORS preflight requests before routing
+    if request.method() == Method::OPTIONS {
+        return with_cors_origin(Response::builder().status(204), origin)
+            .header("access-control-allow-methods", "GET, POST, PUT, DELETE, OPTIONS")
+            .header("access-control-allow-headers", "Content-Type, Authorization")
+            .header("access-control-max-age", "86400") // 24 hours
+            .body(Body::Empty)
+            .map_err(Error::from);
+    }
+
+    // Refuse oversized bodies before any routing or upstream work
+    if request.body().len() > MAX_BODY_BYTES {
+        return error_response(&ProxyError::PayloadTooLarge, origin);
+    }
+
+    if let Err(rate_limited) = get_rate_limiter().check(&client_ip(&request)).await {
+        return error_response(&rate_limited, origin);
+    }
+
+    // Prometheus text, not JSON, so it is answered outside the JSON router
+    if path == "/metrics" {
+        return match require_admin(request.headers()) {
+            Ok(()) => metrics_response(origin),
+            Err(e) => error_response(&e, origin),
+        };
+    }
+
+    // Newline-delimited JSON, which the JSON envelope, ETags and ?fields= do not apply to
+    if path == "/element-summary/ndjson" {
+        let body = async {
+            require_enabled(feature_flags().bulk_endpoint)?;
+            element_summary_ndjson(parse_bulk_ids(&Method::GET, &[], request.uri().query())?).await
+        };
+        return match body.await {
+            Ok(body) => ndjson_response(body, origin),
+            Err(e) => error_response(&e, origin),
+        };
+    }
+
+    // Plain text as well, and the same for every client
+    if path == "/robots.txt" {
+        return robots_response();
+    }
+    // Browsers ask for it unprompted; answering keeps 404s out of the logs
+    if path == "/favicon.ico" {
+        return favicon_response();
+    }
+
+    let callback = match jsonp_callback(request.uri().query()) {
+        Ok(callback) => callback,
+        Err(e) => return error_response(&e, origin),
+    };
+    let pretty = match pretty_requested(request.uri().query(), debug_mode()) {
+        Ok(pretty) => pretty,
+        Err(e) => return error_response(&e, origin),
+    };
+    // Refuse before any upstream work; JSONP bodies are scripts whatever Accept says
+    let format = match callback {
+        Some(_) => BodyFormat::Json,
+        None => match negotiate_format(request.headers()) {
+            Ok(format) => format,
+            Err(e) => return error_response(&e, origin),
+        },
+    };
+
+    // Route matching and handling, with the cache bypass visible to every cache lookup
+    let result = CACHE_BYPASS.scope(wants_fresh_data(request.headers()), router().dispatch(&request)).await;
+
+    // Project ?fields= after the cache so every selection shares one cached body
+    let result = match requested_fields(request.uri().query()) {
+        Some(fields) => result.map(|payload| {
+            let data = project_fields(payload.data, &fields);
+            Payload {
+                etag: compute_etag(&data),
+                data,
+                ..payload
+            }
+        }),
+        None => result,
+    };
+
+    // Serialize successful results up front so serialization failures share the error path;
+    // the ETag stays that of the data alone
+    let result = result.and_then(|mut payload| {
+        let body = with_data_source(std::mem::take(&mut payload.data), payload.source);
+        encode_body(&body, callback.as_deref(), format, pretty).map(|(content_type, body)| (content_type, body, payload))
+    });
+
+    // Convert result to Response
+    match result {
+        Ok((_, _, payload))
+            if is_not_modified(
+                if_none_match.as_deref(),
+                if_modified_since.as_deref(),
+                &payload.etag,
+                payload.last_modified.as_deref(),
+            ) =>
+        {
+            let mut builder = Response::builder()
+                .status(304)
+                .header("etag", payload.etag)
+                .header("x-cache-status", payload.cache_status.as_str());
+            if let Some(last_modified) = payload.last_modified {
+                builder = builder.header("last-modified", last_modified);
+            }
+            if callback.is_none() {
+                builder = with_cors_origin(builder, origin);
+            }
+            builder
+                .header("cache-control", "public, max-age=300") // 5 minutes cache
+                .body(Body::Empty)
+                .map_err(Error::from)
+        }
+        Ok((content_type, body, payload)) => {
+            let (body, content_encoding) = compress_if_accepted(request.headers(), Bytes::from(body));
+
+            // Byte length of the body as sent, i.e. after compression
+            let mut builder = Response::builder()
+                .status(200)
+                .header("content-type", content_type)
+                .header("content-length", body.len())
+                .header("etag", payload.etag)
+                .header("x-cache-status", payload.cache_status.as_str());
+            if let Some(last_modified) = payload.last_modified {
+                builder = builder.header("last-modified", last_modified);
+            }
+            for (name, value) in payload.headers {
+                builder = builder.header(name, value);
+            }
+            // JSONP is loaded by <script> tags, which need no CORS headers
+            if callback.is_none() {
+                builder = with_cors_origin(builder, origin)
+                    .header("access-control-allow-methods", "GET, POST, PUT, DELETE, OPTIONS")
+                    .header("access-control-allow-headers", "Content-Type, Authorization");
+            }
+            let mut builder = builder
+                .header("cache-control", "public, max-age=300") // 5 minutes cache
+                .header("vary", "Accept-Encoding, Accept");
+
+            if let Some(encoding) = content_encoding {
+                builder = builder.header("content-encoding", encoding);
+            }
+
+            // HEAD gets the same headers as GET, including the length of the omitted body
+            if request.method() == Method::HEAD {
+                return builder.body(Body::Empty).map_err(Error::from);
+            }
+
+            builder.body(Body::from(body.to_vec())).map_err(Error::from)
+        }
+        Err(proxy_error) => error_response(&proxy_error, origin),
+    }
+}
+
+// MSRV policy: the minimum supported Rust version is the `rust-version` in
+// Cargo.toml, and CI builds the locked dependency tree with exactly that
+// toolchain. It tracks the newest requirement among the locked dependencies
+// (this crate's own code needs 1.82), so it is only raised together with a
+// Cargo.lock update and never in a patch release.
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    init_tracing();
+    validate_backup_data();
+
+    // Warm up in the background; run() never returns, so never wait on it.
+    // Its cache writes count as in-flight work for shutdown.
+    let warm_up = tokio::spawn(async {
+        let _in_flight = SHUTDOWN.begin().await;
+        warm_cache().await
+    });
+    tokio::spawn(async move {
+        if tokio::time::timeout(Duration::from_secs(WARM_UP_DEADLINE), warm_up).await.is_err() {
+            warn!("Cache warm-up still running after {} s, serving requests without it", WARM_UP_DEADLINE);
+        }
+    });
+
+    // Ctrl-C in local development drains in-flight requests before exiting
+    tokio::select! {
+        result = run(handler) => result,
+        () = shutdown_on_ctrl_c() => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use wiremock::{
+        matchers::{method, path, query_param},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    fn headers_with(name: &'static str, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn compress_if_accepted_gzips_large_bodies() {
+        let raw = serde_json::to_vec(&load_backup_data("bootstrap-static").unwrap()).unwrap();
+        let headers = headers_with("accept-encoding", "deflate, gzip;q=0.8, br");
+
+        let (compressed, encoding) = compress_if_accepted(&headers, Bytes::from(raw.clone()));
+
+        assert_eq!(encoding, Some("gzip"));
+        assert!(compressed.len() < raw.len());
+
+        let mut decompressed = Vec::new();
+        GzDecoder::new(&compressed[..]).read_to_end(&mut decompressed).unwrap();
+        assert_eq!(decompressed, raw);
+    }
+
+    #[tokio::test]
+    async fn retry_with_backoff_retries_server_errors() {
+        let attempts = AtomicU32::new(0);
+        let mock_client = || async {
+            attempts.fetch_add(1, Ordering::Relaxed);
+            Err::<u16, u16>(503)
+        };
+
+        let started = Instant::now();
+        let outcome = retry_with_backoff(mock_client, |outcome| matches!(outcome, Err(status) if *status >= 500)).await;
+
+        assert_eq!(outcome, Err(503));
+        assert_eq!(attempts.load(Ordering::Relaxed), PRIMARY_MAX_RETRIES + 1);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    // Collects the fields recorded on `upstream_fetch` spans
+    #[derive(Clone, Default)]
+    struct UpstreamSpanFields(Arc<std::sync::Mutex<Vec<(String, String)>>>);
+
+    impl tracing::field::Visit for UpstreamSpanFields {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn fmt::Debug) {
+            self.0.lock().unwrap().push((field.name().to_string(), format!("{:?}", value)));
+        }
+    }
+
+    impl<S> tracing_subscriber::Layer<S> for UpstreamSpanFields
+    where
+        S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    {
+        fn on_new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+            _: &tracing::span::Id,
+            _: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            if attrs.metadata().name() == "upstream_fetch" {
+                attrs.record(&mut self.clone());
+            }
+        }
+
+        fn on_record(
+            &self,
+            id: &tracing::span::Id,
+            values: &tracing::span::Record<'_>,
+            ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            if ctx.span(id).is_some_and(|span| span.name() == "upstream_fetch") {
+                values.record(&mut self.clone());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn traced_send_records_status_and_duration_on_its_span() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/traced/"))
+            .respond_with(ResponseTemplate::new(418))
+            .mount(&server)
+            .await;
+        let fields = UpstreamSpanFields::default();
+        let _subscriber = tracing::subscriber::set_default(tracing_subscriber::registry().with(fields.clone()));
+
+        let url = format!("{}/traced/", server.uri());
+        let response = traced_send("backup", &url, Client::new().get(&url)).await.unwrap();
+        assert_eq!(response.status(), 418);
+
+        let recorded = fields.0.lock().unwrap().clone();
+        let field = |name: &str| recorded.iter().find(|(key, _)| key == name).map(|(_, value)| value.clone());
+        assert_eq!(field("url"), Some(url));
+        assert_eq!(field("source").as_deref(), Some("\"backup\""));
+        assert_eq!(field("status").as_deref(), Some("418"));
+        assert!(field("duration_ms").is_some_and(|ms| ms.parse::<u64>().is_ok()));
+    }
+
+    #[tokio::test]
+    async fn retry_with_backoff_stops_on_client_errors() {
+        let attempts = AtomicU32::new(0);
+        let mock_client = || async {
+            attempts.fetch_add(1, Ordering::Relaxed);
+            Err::<u16, u16>(404)
+        };
+
+        let outcome = retry_with_backoff(mock_client, |outcome| matches!(outcome, Err(status) if *status >= 500)).await;
+
+        assert_eq!(outcome, Err(404));
+        assert_eq!(attempts.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn validate_numeric_id_rejects_non_numeric_input() {
+        assert_eq!(validate_numeric_id("302").unwrap(), 302);
+        for input in ["", "-1", "12a", "..", "../../etc/passwd", "1/2", "99999999999"] {
+            let err = validate_numeric_id(input).unwrap_err();
+            assert_eq!(err.status_code(), 400, "{:?} should be rejected", input);
+        }
+    }
+
+    #[test]
+    fn fpl_endpoint_paths_match_upstream() {
+        let cases = [
+            (FplEndpoint::BootstrapStatic, "/bootstrap-static/"),
+            (FplEndpoint::Fixtures, "/fixtures/"),
+            (FplEndpoint::FixturesByGameweek(5), "/fixtures/?event=5"),
+            (FplEndpoint::ElementSummary(302), "/element-summary/302/"),
+            (FplEndpoint::LiveEvent(12), "/event/12/live/"),
+            (FplEndpoint::Picks { manager_id: 123, gw: 4 }, "/entry/123/event/4/picks/"),
+            (FplEndpoint::DreamTeam(7), "/dream-team/7/"),
+            (FplEndpoint::ManagerInfo(123), "/entry/123/"),
+            (FplEndpoint::ManagerTransfers(123), "/entry/123/transfers/"),
+            (FplEndpoint::ManagerHistory(123), "/entry/123/history/"),
+            (FplEndpoint::MyTeam(123), "/my-team/123/"),
+            (FplEndpoint::ManagerCup(123), "/entry/123/cup-status/"),
+            (FplEndpoint::Transfers, "/transfers/"),
+            (FplEndpoint::LeagueStandings { league_id: 314, page: 2 }, "/leagues-classic/314/standings/?page_standings=2"),
+            (FplEndpoint::LeagueStandingsByPhase { league_id: 314, phase: 3 }, "/leagues-classic/314/standings/?page_standings=1&phase=3"),
+            (FplEndpoint::LeagueNewEntries { league_id: 314, page: 2 }, "/leagues-classic/314/standings/?page_new_entries=2"),
+            (FplEndpoint::H2hStandings { league_id: 99, page: 1 }, "/leagues-h2h/99/standings/?page_standings=1"),
+            (FplEndpoint::H2hMatches { league_id: 99, page: 2 }, "/leagues-h2h-matches/league/99/?page=2"),
+        ];
+
+        for (endpoint, path) in cases {
+            assert_eq!(endpoint.path(), path);
+        }
+    }
+
+    #[test]
+    fn fpl_endpoint_backups_exist() {
+        assert!(FplEndpoint::BootstrapStatic.backup_url().unwrap().ends_with("/bootstrap-static.json"));
+        assert!(FplEndpoint::Fixtures.backup_url().unwrap().ends_with("/fixtures.json"));
+        assert_eq!(FplEndpoint::ManagerInfo(1).backup_url(), None);
+
+        for endpoint in [FplEndpoint::BootstrapStatic, FplEndpoint::Fixtures, FplEndpoint::LiveEvent(1), FplEndpoint::DreamTeam(1), FplEndpoint::H2hStandings { league_id: 1, page: 1 }, FplEndpoint::H2hMatches { league_id: 1, page: 1 }] {
+            let key = endpoint.local_backup().unwrap();
+            assert!(load_backup_data(key).is_some(), "backup data for {} should parse", key);
+            assert!(BACKUP_DATA_ENDPOINTS.contains(&key), "{} is not validated at startup", key);
+        }
+        validate_backup_data();
+    }
+
+    #[test]
+    fn openapi_document_parses() {
+        let payload = handle_openapi().unwrap();
+        assert_eq!(payload.data["openapi"], "3.1.0");
+        assert!(payload.data["paths"]["/bootstrap-static"]["get"]["x-cached"].as_bool().unwrap());
+        assert_eq!(payload.cache_status, CacheStatus::Bypass);
+    }
+
+    #[test]
+    fn parse_bulk_ids_reads_query_and_body() {
+        assert_eq!(parse_bulk_ids(&Method::GET, b"", Some("ids=1,2,3")).unwrap(), vec![1, 2, 3]);
+        assert_eq!(parse_bulk_ids(&Method::GET, b"", Some("x=1&ids=4%2C5")).unwrap(), vec![4, 5]);
+        assert_eq!(parse_bulk_ids(&Method::POST, b"[7, 8]", None).unwrap(), vec![7, 8]);
+    }
+
+    #[test]
+    fn parse_bulk_ids_rejects_bad_input() {
+        for (method, body, query) in [
+            (Method::GET, &b""[..], None),
+            (Method::GET, &b""[..], Some("ids=1,abc")),
+            (Method::POST, &b"{\"ids\": [1]}"[..], None),
+            (Method::POST, &b"[-1]"[..], None),
+        ] {
+            let result = parse_bulk_ids(&method, body, query);
+            assert!(matches!(result, Err(ProxyError::InvalidPathParam(_))), "{:?} {:?}", method, query);
+        }
+    }
+
+    #[test]
+    fn element_summary_lines_are_one_json_object_each() {
+        assert_eq!(element_summary_line(7, Ok(json!({"history": []}))), "{\"data\":{\"history\":[]},\"id\":7}\n");
+        let failed: Value = serde_json::from_str(&element_summary_line(8, Err(ProxyError::AllSourcesFailed))).unwrap();
+        assert_eq!(failed["id"], 8);
+        assert!(failed["error"].is_string());
+    }
+
+    #[tokio::test]
+    async fn element_summary_ndjson_rejects_bad_id_lists() {
+        for uri in ["/element-summary/ndjson", "/element-summary/ndjson?ids=1,x"] {
+            let request = http::Request::builder().uri(uri).body(Body::Empty).unwrap();
+            assert_eq!(handler(request).await.unwrap().status(), 400, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn element_summary_bulk_caps_id_count() {
+        let ids = (1..=BULK_MAX_IDS as u32 + 1).collect();
+        assert!(matches!(handle_element_summary_bulk(ids).await, Err(ProxyError::InvalidPathParam(_))));
+        assert!(matches!(handle_element_summary_bulk(Vec::new()).await, Err(ProxyError::InvalidPathParam(_))));
+    }
+
+    #[test]
+    fn extract_automatic_subs_returns_array() {
+        let picks = json!({
+            "picks": [],
+            "automatic_subs": [{"entry": 1, "element_in": 10, "element_out": 20, "event": 3}]
+        });
+        let subs = array_field(&picks, "automatic_subs");
+        assert!(subs.is_array());
+        assert_eq!(subs[0]["element_in"], 10);
+
+        assert_eq!(array_field(&json!({"picks": []}), "automatic_subs"), json!([]));
+    }
+
+    #[tokio::test]
+    async fn probe_upstream_reports_up_and_down() {
+        let server = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .and(path("/ok/"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        Mock::given(method("HEAD"))
+            .and(path("/overloaded/"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        assert_eq!(probe_upstream(&format!("{}/ok/", server.uri())).await, "up");
+        assert_eq!(probe_upstream(&format!("{}/overloaded/", server.uri())).await, "down");
+        assert_eq!(probe_upstream("http://127.0.0.1:9/unreachable/").await, "down");
+    }
+
+    #[test]
+    fn is_not_modified_compares_last_modified() {
+        let modified = Some("Tue, 21 Oct 2025 07:28:00 GMT");
+        assert!(is_not_modified(None, Some("Tue, 21 Oct 2025 07:28:00 GMT"), "\"a\"", modified));
+        assert!(is_not_modified(None, Some("Wed, 22 Oct 2025 00:00:00 GMT"), "\"a\"", modified));
+        assert!(!is_not_modified(None, Some("Mon, 20 Oct 2025 00:00:00 GMT"), "\"a\"", modified));
+        assert!(!is_not_modified(None, Some("not a date"), "\"a\"", modified));
+        assert!(!is_not_modified(None, Some("Wed, 22 Oct 2025 00:00:00 GMT"), "\"a\"", None));
+    }
+
+    #[test]
+    fn is_not_modified_prefers_if_none_match() {
+        let modified = Some("Tue, 21 Oct 2025 07:28:00 GMT");
+        let later = Some("Wed, 22 Oct 2025 00:00:00 GMT");
+        assert!(!is_not_modified(Some("\"b\""), later, "\"a\"", modified));
+        assert!(is_not_modified(Some("\"a\""), None, "\"a\"", None));
+    }
+
+    #[tokio::test]
+    #[cfg_attr(feature = "mock", ignore = "needs upstream HTTP calls")]
+    async fn fetch_all_standings_pages_merges_every_page() {
+        let server = MockServer::start().await;
+        for page in 1..=3u32 {
+            let body = json!({
+                "league": {"id": 314, "name": "Overall"},
+                "standings": {
+                    "has_next": page < 3,
+                    "page": page,
+                    "results": [{"entry": page * 10}, {"entry": page * 10 + 1}]
+                }
+            });
+            Mock::given(method("GET"))
+                .and(path("/leagues-classic/314/standings/"))
+                .and(query_param("page_standings", page.to_string()))
+                .respond_with(ResponseTemplate::new(200).set_body_json(body))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let uri = server.uri();
+        let fetched = fetch_all_standings_pages(|page| format!("{}/leagues-classic/314/standings/?page_standings={}", uri, page))
+            .await
+            .expect("all pages should be fetched");
+
+        let entries: Vec<u64> = fetched.data["standings"]["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|result| result["entry"].as_u64().unwrap())
+            .collect();
+        assert_eq!(entries, vec![10, 11, 20, 21, 30, 31]);
+        assert_eq!(fetched.data["standings"]["has_next"], false);
+        assert_eq!(fetched.data["league"]["name"], "Overall");
+    }
+
+    #[test]
+    fn standings_pages_compare_by_points() {
+        let page = json!({"standings": {"results": [{"total": 1400}, {"total": 1350}, {"total": 1300}]}});
+        assert_eq!(compare_page_points(&page, 1500), Some(std::cmp::Ordering::Less));
+        assert_eq!(compare_page_points(&page, 1350), Some(std::cmp::Ordering::Equal));
+        assert_eq!(compare_page_points(&page, -5), Some(std::cmp::Ordering::Greater));
+        assert_eq!(compare_page_points(&json!({"standings": {"results": []}}), 10), None);
+    }
+
+    #[test]
+    fn neighbouring_pages_alternate_within_bounds() {
+        assert_eq!(neighbouring_pages(4, 10, 2), vec![3, 5, 2, 6]);
+        assert_eq!(neighbouring_pages(4, 4, 3), vec![3, 2, 1]);
+        assert_eq!(neighbouring_pages(1, 1, 3), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn manager_leagues_combine_classic_and_h2h() {
+        let info = json!({
+            "leagues": {
+                "classic": [{"id": 314, "name": "Overall", "entry_rank": 5, "entry_last_rank": 7, "rank_count": 11_000_000}],
+                "h2h": [{"id": 42, "name": "Office", "entry_rank": 1, "entry_last_rank": 2}]
+            }
+        });
+
+        assert_eq!(
+            manager_leagues(&info),
+            vec![
+                json!({"id": 314, "name": "Overall", "type": "classic", "entry_rank": 5, "entry_last_rank": 7}),
+                json!({"id": 42, "name": "Office", "type": "h2h", "entry_rank": 1, "entry_last_rank": 2}),
+            ]
+        );
+        assert!(manager_leagues(&json!({"id": 1})).is_empty());
+    }
+
+    #[test]
+    fn classic_league_rank_reads_the_entry_leagues() {
+        let info = json!({
+            "leagues": {
+                "classic": [
+                    {"id": 314, "entry_rank": 123_456, "rank_count": 11_000_000},
+                    {"id": 900, "entry_rank": 0, "rank_count": 12}
+                ]
+            }
+        });
+
+        assert_eq!(classic_league_rank(&info, 314), Some((123_456, 11_000_000)));
+        assert_eq!(classic_league_rank(&info, 900), None);
+        assert_eq!(classic_league_rank(&info, 1), None);
+        assert_eq!(classic_league_rank(&json!({}), 314), None);
+    }
+
+    #[test]
+    fn simulated_h2h_compares_net_points_of_common_gameweeks() {
+        let history_a = json!({"current": [
+            {"event": 1, "points": 60, "event_transfers_cost": 0},
+            {"event": 2, "points": 50, "event_transfers_cost": 8},
+            {"event": 3, "points": 45, "event_transfers_cost": 0}
+        ]});
+        // Manager B joined in gameweek 2
+        let history_b = json!({"current": [
+            {"event": 2, "points": 42, "event_transfers_cost": 0},
+            {"event": 3, "points": 51, "event_transfers_cost": 0}
+        ]});
+
+        assert_eq!(
+            simulated_h2h(&history_a, &history_b),
+            json!({
+                "wins_a": 0,
+                "wins_b": 1,
+                "draws": 1,
+                "history": [
+                    {"gw": 2, "score_a": 42, "score_b": 42, "winner": "draw"},
+                    {"gw": 3, "score_a": 45, "score_b": 51, "winner": "b"}
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn find_standing_matches_the_entry_id() {
+        let page = json!({
+            "standings": {
+                "results": [
+                    {"entry": 10, "rank": 1, "entry_name": "Top", "total": 80},
+                    {"entry": 42, "rank": 2, "entry_name": "Mine", "total": 75}
+                ]
+            }
+        });
+
+        assert_eq!(find_standing(&page, 42).unwrap()["entry_name"], "Mine");
+        assert!(find_standing(&page, 7).is_none());
+    }
+
+    #[tokio::test]
+    async fn shutdown_waits_for_in_flight_work_then_refuses_new_work() {
+        let shutdown = Arc::new(Shutdown::new(4));
+        let permit = shutdown.begin().await.unwrap();
+
+        let draining = {
+            let shutdown = shutdown.clone();
+            tokio::spawn(async move { shutdown.drain(Duration::from_secs(5)).await })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!draining.is_finished());
+        assert!(shutdown.begin().await.is_none());
+
+        drop(permit);
+        assert_eq!(draining.await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn shutdown_gives_up_after_the_grace_period() {
+        let shutdown = Shutdown::new(4);
+        let _stuck = shutdown.begin().await.unwrap();
+        assert_eq!(shutdown.drain(Duration::from_millis(10)).await, 1);
+        assert!(shutdown.begin().await.is_none());
+    }
+
+    fn health_request(request_id: Option<&str>) -> Request {
+        let mut builder = http::Request::builder().uri("/health");
+        if let Some(request_id) = request_id {
+            builder = builder.header("x-request-id", request_id);
+        }
+        builder.body(Body::Empty).unwrap()
+    }
+
+    #[test]
+    fn circuit_breaker_lets_one_probe_through_after_cooldown() {
+        let breaker = CircuitBreaker::new(2, Duration::from_millis(50));
+        breaker.record_failure();
+        assert!(!breaker.is_open());
+        breaker.record_failure();
+        assert!(breaker.is_open());
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(!breaker.is_open(), "the first caller after the cool-down probes");
+        assert!(breaker.is_open(), "everyone else waits for the probe");
+
+        // A failed probe keeps it open for another cool-down
+        breaker.record_failure();
+        assert!(breaker.is_open());
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(!breaker.is_open());
+        breaker.record_success();
+        assert!(!breaker.is_open());
+        assert!(!breaker.is_open());
+    }
+
+    #[tokio::test]
+    async fn handler_echoes_incoming_request_id() {
+        let response = handler(health_request(Some("client-trace-42"))).await.unwrap();
+        assert_eq!(response.headers()["x-request-id"], "client-trace-42");
+    }
+
+    #[tokio::test]
+    async fn handler_reports_proxy_version_on_success_and_error() {
+        let unknown = http::Request::builder().uri("/nope").body(Body::Empty).unwrap();
+        for response in [handler(health_request(None)).await.unwrap(), handler(unknown).await.unwrap()] {
+            let version = response.headers()["x-proxy-version"].to_str().unwrap();
+            assert!(!version.is_empty());
+            assert_eq!(version, env!("CARGO_PKG_VERSION"));
+        }
+    }
+
+    #[tokio::test]
+    async fn handler_generates_request_id_when_absent() {
+        let response = handler(health_request(None)).await.unwrap();
+        let request_id = response.headers()["x-request-id"].to_str().unwrap();
+        assert_eq!(Uuid::parse_str(request_id).unwrap().get_version_num(), 4);
+    }
+
+    #[test]
+    fn feature_flags_default_to_enabled() {
+        let flags = FeatureFlags::from_lookup(|_| None);
+        assert!(flags.bulk_endpoint && flags.h2h_endpoint && flags.league_all_endpoint);
+    }
+
+    #[test]
+    fn feature_flags_disable_on_explicit_off_values() {
+        for off in ["0", "false", "OFF", " no "] {
+            let flags = FeatureFlags::from_lookup(|name| (name == "ENABLE_H2H_ENDPOINT").then(|| off.to_string()));
+            assert!(!flags.h2h_endpoint, "{:?} should disable the flag", off);
+            assert!(flags.bulk_endpoint);
+        }
+
+        let flags = FeatureFlags::from_lookup(|_| Some("true".to_string()));
+        assert!(flags.bulk_endpoint && flags.h2h_endpoint && flags.league_all_endpoint);
+    }
+
+    #[test]
+    fn pool_settings_read_both_variables() {
+        assert_eq!(
+            PoolSettings::from_lookup(|_| None),
+            PoolSettings { max_idle_per_host: None, idle_timeout: None }
+        );
+
+        let pool = PoolSettings::from_lookup(|name| match name {
+            "FPL_MAX_CONNECTIONS" => Some("10".to_string()),
+            "FPL_CONNECTION_IDLE_TIMEOUT_SECS" => Some(" 30 ".to_string()),
+            _ => None,
+        });
+        assert_eq!(pool, PoolSettings { max_idle_per_host: Some(10), idle_timeout: Some(Duration::from_secs(30)) });
+
+        let invalid = PoolSettings::from_lookup(|_| Some("many".to_string()));
+        assert_eq!(invalid.max_idle_per_host, None);
+    }
+
+    #[test]
+    fn disabled_endpoint_maps_to_service_unavailable() {
+        assert_eq!(require_enabled(false).unwrap_err().status_code(), 503);
+        assert_eq!(ProxyError::EndpointDisabled.to_string(), "endpoint_disabled");
+        assert!(require_enabled(true).is_ok());
+    }
+
+    #[test]
+    fn wants_fresh_data_reads_no_cache_directives() {
+        assert!(wants_fresh_data(&headers_with("cache-control", "no-cache")));
+        assert!(wants_fresh_data(&headers_with("cache-control", "max-age=0, No-Cache")));
+        assert!(wants_fresh_data(&headers_with("pragma", "no-cache")));
+        assert!(!wants_fresh_data(&headers_with("cache-control", "max-age=0")));
+        assert!(!wants_fresh_data(&HeaderMap::new()));
+    }
+
+    #[tokio::test]
+    async fn no_cache_refetches_and_refreshes_cache_entry() {
+        let policy = CachePolicy::new("bypass-test", 600);
+        let key = policy.cache_key(&[&1]);
+        let fetches = AtomicU32::new(0);
+        let fetch = |value: u32| {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            async move {
+                Ok(FetchResult {
+                    data: json!({ "value": value }),
+                    last_modified: None,
+                    source: DataSource::Primary,
+                })
+            }
+        };
+
+        let first = get_cached_or_compute(&key, policy, fetch(1)).await.unwrap();
+        assert_eq!(first.cache_status, CacheStatus::Miss);
+        let cached = get_cached_or_compute(&key, policy, fetch(2)).await.unwrap();
+        assert_eq!(cached.cache_status, CacheStatus::Hit);
+        assert_eq!(cached.data["value"], 1);
+
+        let bypassed = CACHE_BYPASS.scope(true, get_cached_or_compute(&key, policy, fetch(3))).await.unwrap();
+        assert_eq!(bypassed.cache_status, CacheStatus::Bypass);
+        assert_eq!(bypassed.data["value"], 3);
+
+        let refreshed = get_cached_or_compute(&key, policy, fetch(4)).await.unwrap();
+        assert_eq!(refreshed.cache_status, CacheStatus::Hit);
+        assert_eq!(refreshed.data["value"], 3);
+    }
+
+    #[tokio::test]
+    async fn responses_carry_body_byte_length() {
+        let ok = handler(health_request(None)).await.unwrap();
+        assert_eq!(ok.headers()["content-length"], ok.body().len().to_string());
+
+        let not_found = http::Request::builder().uri("/no-such-route").body(Body::Empty).unwrap();
+        let error = handler(not_found).await.unwrap();
+        assert_eq!(error.status(), 404);
+        assert_eq!(error.headers()["content-length"], error.body().len().to_string());
+    }
+
+    #[tokio::test]
+    async fn handler_rejects_oversized_bodies() {
+        let request = http::Request::builder()
+            .method("POST")
+            .uri("/element-summary/bulk")
+            .body(Body::from(vec![b'1'; 128 * 1024]))
+            .unwrap();
+
+        let response = handler(request).await.unwrap();
+        assert_eq!(response.status(), 413);
+    }
+
+    // Shared by every admin test, since they run in parallel against one environment
+    const ADMIN_TEST_TOKEN: &str = "admin-test-token";
+
+    #[tokio::test]
+    async fn metrics_count_handled_requests() {
+        std::env::set_var("ADMIN_TOKEN", ADMIN_TEST_TOKEN);
+        handler(health_request(None)).await.unwrap();
+
+        let unauthorized = http::Request::builder().uri("/metrics").body(Body::Empty).unwrap();
+        assert_eq!(handler(unauthorized).await.unwrap().status(), 401);
+
+        let request = http::Request::builder()
+            .uri("/metrics")
+            .header("authorization", format!("Bearer {}", ADMIN_TEST_TOKEN))
+            .body(Body::Empty)
+            .unwrap();
+        let response = handler(request).await.unwrap();
+        assert_eq!(response.status(), 200);
+
+        let Body::Text(text) = response.body() else {
+            panic!("metrics should be a text body");
+        };
+        assert!(text.contains("proxy_requests_total{path=\"/health\",status=\"200\"}"));
+    }
+
+    #[test]
+    fn metrics_routes_are_patterns_not_raw_paths() {
+        assert_eq!(metrics_route("/picks/123/4"), "/picks/:manager_id/:gw");
+        assert_eq!(metrics_route("/cache/live-event-12"), "/cache/:key");
+        assert_eq!(metrics_route("/picks/123"), "/picks/*");
+        assert_eq!(metrics_route("/element-summary/ndjson"), "/element-summary/ndjson");
+        assert_eq!(metrics_route("/wp-admin"), "unmatched");
+        assert_eq!(metrics_route("/.env"), "unmatched");
+    }
+
+    fn cache_delete_request(key: &str, token: Option<&str>) -> Request {
+        let mut builder = http::Request::builder().method("DELETE").uri(format!("/cache/{}", key));
+        if let Some(token) = token {
+            builder = builder.header("authorization", format!("Bearer {}", token));
+        }
+        builder.body(Body::Empty).unwrap()
+    }
+
+    #[test]
+    fn cache_keys_must_match_a_policy_prefix() {
+        for key in ["teams", "live-event-12", "players?min_price=50&position=MID", "league-all-314?page=2"] {
+            assert!(is_known_cache_key(key), "{}", key);
+        }
+        for key in ["", "unknown-1", "teamsx", "teams-1/2", "teams-1 2", &format!("teams-{}", "1".repeat(CACHE_KEY_MAX_LEN))] {
+            assert!(!is_known_cache_key(key), "{}", key);
+        }
+    }
+
+    #[tokio::test]
+    async fn cache_delete_evicts_present_keys() {
+        std::env::set_var("ADMIN_TOKEN", ADMIN_TEST_TOKEN);
+        let policy = CachePolicy::TEAMS;
+        let key = policy.cache_key(&[&"evict-test"]);
+        let fetch = async {
+            Ok(FetchResult {
+                data: json!({ "value": 1 }),
+                last_modified: None,
+                source: DataSource::Primary,
+            })
+        };
+        get_cached_or_compute(&key, policy, fetch).await.unwrap();
+
+        let response = handler(cache_delete_request("teams-evict%2Dtest", Some(ADMIN_TEST_TOKEN))).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let Body::Binary(bytes) = response.body() else {
+            panic!("responses should be binary bodies");
+        };
+        assert_eq!(serde_json::from_slice::<Value>(bytes).unwrap()["data"], json!({ "evicted": key }));
+        assert!(get_cache().get(&key).await.is_none());
+
+        let again = handler(cache_delete_request(&key, Some(ADMIN_TEST_TOKEN))).await.unwrap();
+        assert_eq!(again.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn cache_delete_rejects_absent_and_unknown_keys() {
+        std::env::set_var("ADMIN_TOKEN", ADMIN_TEST_TOKEN);
+        let absent = handler(cache_delete_request("teams-never-cached", Some(ADMIN_TEST_TOKEN))).await.unwrap();
+        assert_eq!(absent.status(), 404);
+
+        let unknown = handler(cache_delete_request("session-abc", Some(ADMIN_TEST_TOKEN))).await.unwrap();
+        assert_eq!(unknown.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn cache_delete_requires_admin_token_and_delete_method() {
+        std::env::set_var("ADMIN_TOKEN", ADMIN_TEST_TOKEN);
+        for token in [None, Some("wrong-token")] {
+            let response = handler(cache_delete_request("teams", token)).await.unwrap();
+            assert_eq!(response.status(), 401);
+        }
+
+        let get = http::Request::builder().uri("/cache/teams").body(Body::Empty).unwrap();
+        assert_eq!(handler(get).await.unwrap().status(), 404);
+    }
+
+    #[tokio::test]
+    async fn robots_txt_disallows_every_crawler() {
+        let request = http::Request::builder().uri("/robots.txt").body(Body::Empty).unwrap();
+        let response = handler(request).await.unwrap();
+
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["content-type"], "text/plain");
+        assert_eq!(response.headers()["cache-control"], "public, max-age=86400");
+        let Body::Text(text) = response.body() else {
+            panic!("robots.txt should be a text body");
+        };
+        assert_eq!(text, "User-agent: *\nDisallow: /\n");
+    }
+
+    #[tokio::test]
+    async fn favicon_is_a_one_pixel_icon() {
+        let request = http::Request::builder().uri("/favicon.ico").body(Body::Empty).unwrap();
+        let response = handler(request).await.unwrap();
+
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["content-type"], "image/x-icon");
+        assert_eq!(response.headers()["cache-control"], "public, max-age=31536000");
+        let Body::Binary(icon) = response.body() else {
+            panic!("favicon should be a binary body");
+        };
+        assert_eq!(icon[..6], [0, 0, 1, 0, 1, 0]);
+        // The directory entry's size and offset account for every byte
+        let size = u32::from_le_bytes(icon[14..18].try_into().unwrap()) as usize;
+        let offset = u32::from_le_bytes(icon[18..22].try_into().unwrap()) as usize;
+        assert_eq!(offset + size, icon.len());
+    }
+
+    #[test]
+    fn request_id_replaces_oversized_ids() {
+        let oversized = "x".repeat(MAX_REQUEST_ID_LEN + 1);
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", oversized.parse().unwrap());
+        assert!(Uuid::parse_str(&request_id(&headers)).is_ok());
+    }
+
+    #[tokio::test]
+    #[cfg_attr(feature = "mock", ignore = "needs upstream HTTP calls")]
+    async fn fetch_standings_pages_stops_at_max_results() {
+        let server = MockServer::start().await;
+        for (page, expected_calls) in [(1u32, 1u64), (2, 1), (3, 0)] {
+            let body = json!({
+                "standings": {
+                    "has_next": page < 3,
+                    "results": [{"entry": page * 10}, {"entry": page * 10 + 1}]
+                }
+            });
+            Mock::given(method("GET"))
+                .and(path("/leagues-classic/99/standings/"))
+                .and(query_param("page_standings", page.to_string()))
+                .respond_with(ResponseTemplate::new(200).set_body_json(body))
+                .expect(expected_calls)
+                .mount(&server)
+                .await;
+        }
+
+        let uri = server.uri();
+        let fetched = fetch_standings_pages(|page| format!("{}/leagues-classic/99/standings/?page_standings={}", uri, page), 3)
+            .await
+            .expect("pages should be fetched");
+
+        assert_eq!(fetched.data["standings"]["results"].as_array().unwrap().len(), 4);
+        assert_eq!(fetched.data["standings"]["has_next"], true);
+    }
+
+    #[test]
+    fn validate_top_n_enforces_range() {
+        assert_eq!(validate_top_n("1").unwrap(), 1);
+        assert_eq!(validate_top_n("50").unwrap(), 50);
+        for invalid in ["0", "51", "ten"] {
+            assert!(matches!(validate_top_n(invalid), Err(ProxyError::InvalidPathParam(_))), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn validate_gameweek_enforces_season_range() {
+        assert_eq!(validate_gameweek("1").unwrap(), 1);
+        assert_eq!(validate_gameweek("38").unwrap(), 38);
+        for invalid in ["0", "39", "gw1"] {
+            assert!(matches!(validate_gameweek(invalid), Err(ProxyError::InvalidPathParam(_))), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn team_players_from_backup_sort_by_position_then_price() {
+        let bootstrap = backup_bootstrap();
+        let players = team_players(&bootstrap, 1);
+
+        assert!(players.iter().all(|player| player.team == 1));
+        assert_eq!(players.iter().map(|player| player.id).collect::<Vec<_>>(), vec![1, 2, 4, 3, 5]);
+        assert!(team_players(&bootstrap, 2).is_empty());
+        assert!(matches!(validate_team_id("21"), Err(ProxyError::InvalidPathParam(_))));
+        assert!(matches!(validate_team_id("0"), Err(ProxyError::InvalidPathParam(_))));
+    }
+
+    #[test]
+    fn gameweek_bonus_from_backup_sorts_by_bonus() {
+        let live: LiveEvent = serde_json::from_value(load_backup_data("live-event").unwrap()).unwrap();
+        let bootstrap = backup_bootstrap();
+
+        assert_eq!(
+            Value::Array(gameweek_bonus(&live, &bootstrap)),
+            json!([
+                {"player_id": 5, "web_name": "Havertz", "team": 1, "bonus": 3},
+                {"player_id": 3, "web_name": "Ødegaard", "team": 1, "bonus": 2},
+                {"player_id": 2, "web_name": "Saliba", "team": 1, "bonus": 1}
+            ])
+        );
+    }
+
+    #[test]
+    fn top_performers_n_defaults_and_caps() {
+        assert_eq!(top_performers_n(None).unwrap(), 10);
+        assert_eq!(top_performers_n(Some("n=3")).unwrap(), 3);
+        assert_eq!(top_performers_n(Some("n=500")).unwrap(), 50);
+        for invalid in ["n=0", "n=ten", "n=-1"] {
+            assert!(matches!(top_performers_n(Some(invalid)), Err(ProxyError::InvalidPathParam(_))), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn top_performers_from_backup_rank_by_points() {
+        let live: LiveEvent = serde_json::from_value(load_backup_data("live-event").unwrap()).unwrap();
+        let bootstrap = backup_bootstrap();
+
+        let top = top_performers(&live, &bootstrap, 3);
+        assert_eq!(
+            top[0],
+            json!({"player_id": 5, "web_name": "Havertz", "team": 1, "points": 9, "goals": 1, "assists": 0, "bonus": 3})
+        );
+        let ids: Vec<u64> = top.iter().map(|player| player["player_id"].as_u64().unwrap()).collect();
+        // Raya and Saliba tie on 7 points, so the lower ID comes first
+        assert_eq!(ids, vec![5, 3, 1]);
+    }
+
+    fn fixture_rows(points: &[i64]) -> Vec<Value> {
+        points
+            .iter()
+            .enumerate()
+            .map(|(i, points)| json!({"round": i + 1, "total_points": points, "bps": points * 4, "minutes": 90}))
+            .collect()
+    }
+
+    #[test]
+    fn latest_transfers_keep_only_the_last_gameweek() {
+        let transfers = [
+            json!({"element_in": 5, "element_out": 4, "event": 7}),
+            json!({"element_in": 3, "element_out": 2, "event": 12}),
+            json!({"element_in": 1, "element_out": 6, "event": 12}),
+            json!({"element_in": 8, "element_out": 9, "event": 3}),
+        ];
+        let latest = latest_transfers(&transfers);
+        assert_eq!(latest, vec![transfers[1].clone(), transfers[2].clone()]);
+        assert!(latest_transfers(&[]).is_empty());
+    }
+
+    #[test]
+    fn form_trend_compares_last_five_with_last_ten() {
+        // Last ten average 5.5, last five average 9
+        let improving = form_trend(&fixture_rows(&[2, 2, 2, 2, 2, 8, 9, 10, 9, 9]));
+        assert_eq!(improving["trend"], "improving");
+        assert_eq!(improving["recent_avg_points"], 9.0);
+        assert_eq!(improving["recent_avg_bps"], 36.0);
+        assert_eq!(improving["baseline_avg_points"], 5.5);
+        let rounds: Vec<u64> = improving["gws"].as_array().unwrap().iter().map(|gw| gw["round"].as_u64().unwrap()).collect();
+        assert_eq!(rounds, vec![6, 7, 8, 9, 10]);
+
+        // Only fixtures outside the last ten do not count
+        assert_eq!(form_trend(&fixture_rows(&[15, 9, 9, 9, 9, 9, 2, 2, 2, 2, 2]))["trend"], "declining");
+        assert_eq!(form_trend(&fixture_rows(&[5, 5, 6]))["trend"], "stable");
+
+        // Negative scores count against the average rather than as zero
+        let declining = form_trend(&fixture_rows(&[5, 5, 5, 5, 5, -1, -2, 1, 0, -3]));
+        assert_eq!(declining["trend"], "declining");
+        assert_eq!(declining["recent_avg_points"], -1.0);
+        assert_eq!(declining["recent_avg_bps"], -4.0);
+        assert_eq!(declining["baseline_avg_points"], 2.0);
+    }
+
+    #[test]
+    fn finished_fixtures_all_have_scores() {
+        let Some(Value::Array(fixtures)) = load_backup_data("fixtures") else {
+            panic!("backup fixtures should be an array");
+        };
+        let results = finished_fixtures(fixtures);
+
+        assert_eq!(results.len(), 1);
+        for result in &results {
+            assert!(result["team_h_score"].is_u64() && result["team_a_score"].is_u64(), "{}", result);
+        }
+    }
+
+    #[test]
+    fn price_changes_compare_consecutive_fixtures() {
+        let history = [
+            json!({"round": 1, "value": 55}),
+            json!({"round": 2, "value": 55}),
+            json!({"round": 3, "value": 56}),
+            json!({"round": 3, "value": 56}),
+            json!({"round": 4, "value": 54}),
+        ];
+        assert_eq!(
+            price_changes(&history),
+            vec![
+                json!({"gw": 3, "old_price": 55, "new_price": 56, "change": 1}),
+                json!({"gw": 4, "old_price": 56, "new_price": 54, "change": -2}),
+            ]
+        );
+        assert!(price_changes(&history[..2]).is_empty());
+    }
+
+    #[test]
+    fn form_trend_without_fixtures_is_stable_and_empty() {
+        let trend = form_trend(&[]);
+        assert_eq!(trend["trend"], "stable");
+        assert!(trend["recent_avg_points"].is_null());
+        assert_eq!(trend["gws"], json!([]));
+    }
+
+    #[test]
+    fn differential_threshold_defaults_and_validates() {
+        assert_eq!(differential_threshold(None).unwrap(), 10.0);
+        assert_eq!(differential_threshold(Some("threshold=5.5")).unwrap(), 5.5);
+        for invalid in ["threshold=0", "threshold=101", "threshold=ten", "threshold=NaN"] {
+            assert!(
+                matches!(differential_threshold(Some(invalid)), Err(ProxyError::InvalidPathParam(_))),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn differentials_from_backup_keep_low_ownership_players() {
+        let live: LiveEvent = serde_json::from_value(load_backup_data("live-event").unwrap()).unwrap();
+        let bootstrap = backup_bootstrap();
+
+        // Havertz (6.2%) and Ødegaard (8.4%) are the only players under 10%
+        let picks = differentials(&live, &bootstrap, 10.0);
+        assert_eq!(
+            picks[0],
+            json!({"player_id": 5, "web_name": "Havertz", "team": 1, "selected_by_percent": 6.2, "points": 9})
+        );
+        let ids: Vec<u64> = picks.iter().map(|player| player["player_id"].as_u64().unwrap()).collect();
+        assert_eq!(ids, vec![5, 3]);
+
+        assert!(differentials(&live, &bootstrap, 5.0).is_empty());
+    }
+
+    #[test]
+    fn captain_choices_rank_by_ownership_and_flag_most_captained() {
+        let live: LiveEvent = serde_json::from_value(load_backup_data("live-event").unwrap()).unwrap();
+        let mut bootstrap = backup_bootstrap();
+        bootstrap.events[0].extra.insert("most_captained".to_string(), json!(2));
+
+        let choices = captain_choices(&live, &bootstrap, 1);
+        assert_eq!(
+            choices[0],
+            json!({
+                "player_id": 4, "web_name": "Saka", "team": 1, "selected_by_percent": 31.7,
+                "points": 2, "captain_points": 4, "most_captained": false
+            })
+        );
+        let ids: Vec<u64> = choices.iter().map(|player| player["player_id"].as_u64().unwrap()).collect();
+        assert_eq!(ids, vec![4, 2, 1, 3, 5]);
+        assert_eq!(choices[1]["most_captained"], true);
+
+        // Other gameweeks do not inherit gameweek 1's most captained player
+        assert!(captain_choices(&live, &bootstrap, 2).iter().all(|player| player["most_captained"] == false));
+    }
+
+    #[test]
+    fn season_history_maps_phases() {
+        let bootstrap: BootstrapStatic = serde_json::from_value(json!({
+            "events": [],
+            "teams": [],
+            "elements": [],
+            "phases": [
+                {"id": 1, "name": "Overall", "start_event": 1, "stop_event": 38},
+                {"id": 2, "name": "August", "start_event": 1, "stop_event": 3}
+            ],
+            "total_players": 11_000_000
+        }))
+        .unwrap();
+
+        assert_eq!(
+            season_history(&bootstrap),
+            json!([
+                {"season_id": 1, "name": "Overall", "total_players": 11_000_000},
+                {"season_id": 2, "name": "August", "total_players": 11_000_000}
+            ])
+        );
+    }
+
+    #[test]
+    fn cors_allow_origin_defaults_to_wildcard() {
+        assert_eq!(cors_allow_origin(None, Some("https://example.com")), Some("*"));
+        assert_eq!(cors_allow_origin(None, None), Some("*"));
+    }
+
+    #[test]
+    fn cors_allow_origin_omits_header_for_same_origin_requests() {
+        // Browsers send no Origin header on same-origin GETs, so there is nothing to reflect
+        let allowed = parse_allowed_origins(Some("https://app.example.com"));
+        assert_eq!(cors_allow_origin(allowed.as_deref(), None), None);
+    }
+
+    #[test]
+    fn cors_allow_origin_reflects_allowed_cross_origin() {
+        let allowed = parse_allowed_origins(Some("https://app.example.com, https://admin.example.com/"));
+        assert_eq!(
+            cors_allow_origin(allowed.as_deref(), Some("https://admin.example.com")),
+            Some("https://admin.example.com")
+        );
+    }
+
+    #[test]
+    fn cors_allow_origin_rejects_disallowed_cross_origin() {
+        let allowed = parse_allowed_origins(Some("https://app.example.com"));
+        assert_eq!(cors_allow_origin(allowed.as_deref(), Some("https://evil.example.com")), None);
+        assert_eq!(cors_allow_origin(allowed.as_deref(), Some("https://app.example.com.evil.com")), None);
+    }
+
+    #[test]
+    fn compress_if_accepted_skips_without_gzip() {
+        let raw = Bytes::from(vec![b'a'; MIN_COMPRESS_BYTES * 2]);
+
+        let (body, encoding) = compress_if_accepted(&HeaderMap::new(), raw.clone());
+        assert_eq!(encoding, None);
+        assert_eq!(body, raw);
+
+        let refused = headers_with("accept-encoding", "gzip;q=0");
+        assert_eq!(compress_if_accepted(&refused, raw).1, None);
+    }
+
+    #[test]
+    fn negotiate_format_follows_q_values() {
+        let format = |accept| negotiate_format(&headers_with("accept", accept)).unwrap();
+        assert_eq!(format("application/json, application/msgpack;q=0.1"), BodyFormat::Json);
+        assert_eq!(format("application/json, application/msgpack"), BodyFormat::Json);
+        assert_eq!(format("application/json;q=0.5, application/msgpack"), BodyFormat::Msgpack);
+        assert_eq!(format("*/*;q=0.1, application/msgpack"), BodyFormat::Msgpack);
+        assert_eq!(format("application/msgpack;q=0, application/json;q=0.2"), BodyFormat::Json);
+        // The most specific range wins, so application/json;q=0 refuses JSON despite application/*
+        assert_eq!(format("application/*;q=0.8, application/json;q=0, text/json;q=0.3"), BodyFormat::Msgpack);
+        assert!(matches!(negotiate_format(&headers_with("accept", "application/msgpack;q=0")), Err(ProxyError::NotAcceptable)));
+    }
+
+    #[test]
+    fn negotiate_format_prefers_application_json() {
+        let format = |accept| negotiate_format(&headers_with("accept", accept));
+        assert_eq!(negotiate_format(&HeaderMap::new()).unwrap(), BodyFormat::Json);
+        assert_eq!(format("text/json, application/json").unwrap(), BodyFormat::Json);
+        assert_eq!(format("*/*").unwrap(), BodyFormat::Json);
+        assert_eq!(format("text/json").unwrap(), BodyFormat::TextJson);
+        assert_eq!(format("application/json;q=0, text/*").unwrap(), BodyFormat::TextJson);
+        assert_eq!(format("application/msgpack").unwrap(), BodyFormat::Msgpack);
+        assert!(matches!(format("text/html"), Err(ProxyError::NotAcceptable)));
+        assert!(matches!(format("application/json; q=0.0"), Err(ProxyError::NotAcceptable)));
+    }
+
+    #[tokio::test]
+    async fn handler_refuses_unacceptable_media_types() {
+        let (content_type, _) = openapi_body(Some("text/json")).await;
+        assert_eq!(content_type, "text/json");
+
+        let request = http::Request::builder().uri("/health").header("accept", "text/html").body(Body::Empty).unwrap();
+        let response = handler(request).await.unwrap();
+        assert_eq!(response.status(), 406);
+        assert_eq!(response.headers()["content-type"], "application/json");
+    }
+
+    async fn openapi_body(accept: Option<&str>) -> (String, Vec<u8>) {
+        let mut builder = http::Request::builder().uri("/openapi.json");
+        if let Some(accept) = accept {
+            builder = builder.header("accept", accept);
+        }
+        let response = handler(builder.body(Body::Empty).unwrap()).await.unwrap();
+        let content_type = response.headers()["content-type"].to_str().unwrap().to_string();
+        let Body::Binary(bytes) = response.into_body() else {
+            panic!("responses should be binary bodies");
+        };
+        (content_type, bytes)
+    }
+
+    #[tokio::test]
+    async fn handler_encodes_msgpack_when_accepted() {
+        let (json_type, json_body) = openapi_body(None).await;
+        let (msgpack_type, msgpack_body) = openapi_body(Some("application/msgpack")).await;
+
+        assert_eq!(json_type, "application/json");
+        assert_eq!(msgpack_type, "application/msgpack");
+        let decoded: Value = rmp_serde::from_slice(&msgpack_body).unwrap();
+        assert_eq!(decoded, serde_json::from_slice::<Value>(&json_body).unwrap());
+        assert!(msgpack_body.len() < json_body.len());
+    }
+
+    fn fixtures(count: u64) -> Vec<Value> {
+        (1..=count).map(|id| json!({ "id": id })).collect()
+    }
+
+    #[test]
+    fn pagination_is_absent_without_params() {
+        assert_eq!(Pagination::from_query(Some("event=3")).unwrap(), None);
+        assert_eq!(forwarded_query(Some("limit=5&offset=10")), None);
+        assert_eq!(forwarded_query(Some("limit=5&event=3")).as_deref(), Some("event=3"));
+    }
+
+    #[test]
+    fn pagination_rejects_invalid_params() {
+        assert!(matches!(Pagination::from_query(Some("limit=51")), Err(ProxyError::InvalidPathParam(_))));
+        assert!(matches!(Pagination::from_query(Some("offset=-1")), Err(ProxyError::InvalidPathParam(_))));
+        assert!(matches!(Pagination::from_query(Some("limit=ten")), Err(ProxyError::InvalidPathParam(_))));
+    }
+
+    #[test]
+    fn pagination_of_empty_result_is_empty() {
+        let page = Pagination { limit: Some(10), offset: 0 };
+        assert!(page.apply(Vec::new()).is_empty());
+        assert_eq!(page.link_header("/fixtures", None, 0), None);
+    }
+
+    #[test]
+    fn pagination_offset_beyond_total_is_empty() {
+        let page = Pagination { limit: Some(10), offset: 30 };
+        assert!(page.apply(fixtures(20)).is_empty());
+        assert_eq!(
+            page.link_header("/fixtures", Some("offset=30&limit=10"), 20).as_deref(),
+            Some("</fixtures?limit=10&offset=20>; rel=\"prev\"")
+        );
+    }
+
+    #[test]
+    fn pagination_limit_zero_returns_nothing() {
+        let page = Pagination::from_query(Some("limit=0")).unwrap().unwrap();
+        assert!(page.apply(fixtures(5)).is_empty());
+        assert_eq!(page.link_header("/fixtures", Some("limit=0"), 5), None);
+    }
+
+    #[test]
+    fn pagination_links_next_and_prev_pages() {
+        let page = Pagination { limit: Some(10), offset: 10 };
+        assert_eq!(page.apply(fixtures(35)), fixtures(20)[10..].to_vec());
+        assert_eq!(
+            page.link_header("/fixtures/gameweek/3", Some("event=3&limit=10&offset=10"), 35).as_deref(),
+            Some(
+                "</fixtures/gameweek/3?event=3&limit=10&offset=20>; rel=\"next\", \
+                 </fixtures/gameweek/3?event=3&limit=10&offset=0>; rel=\"prev\""
+            )
+        );
+    }
+
+    fn backup_bootstrap() -> BootstrapStatic {
+        serde_json::from_value(load_backup_data("bootstrap-static").unwrap()).unwrap()
+    }
+
+    #[test]
+    fn search_players_matches_web_and_full_names() {
+        let bootstrap = backup_bootstrap();
+        let names = |query| search_players(&bootstrap, query).iter().map(|player| player.id).collect::<Vec<_>>();
+
+        assert_eq!(names("saka"), vec![4]);
+        assert_eq!(names("BUKAYO SAKA"), vec![4]);
+        assert_eq!(names("ødegaard"), vec![3]);
+        assert_eq!(names("sa"), vec![2, 4]);
+        assert!(names("haaland").is_empty());
+    }
+
+    #[test]
+    fn filter_players_applies_every_filter() {
+        let bootstrap = backup_bootstrap();
+        let ids = |min, max, position, team| {
+            filter_players(&bootstrap, min, max, position, team).iter().map(|player| player.id).collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids(None, None, None, None), vec![1, 2, 3, 4, 5]);
+        assert_eq!(ids(Some(60), Some(85), None, None), vec![2, 3, 5]);
+        assert_eq!(ids(None, Some(90), Some(3), Some(1)), vec![3]);
+        assert!(ids(None, None, None, Some(2)).is_empty());
+    }
+
+    #[test]
+    fn players_query_params_must_be_integers() {
+        assert_eq!(u32_query_param(Some("min_price=45&team=1"), "min_price").unwrap(), Some(45));
+        assert_eq!(u32_query_param(Some("team=1"), "position").unwrap(), None);
+        assert!(matches!(u32_query_param(Some("max_price=8.5"), "max_price"), Err(ProxyError::InvalidPathParam(_))));
+    }
+
+    #[test]
+    fn gameweek_transfers_rank_the_current_gameweek() {
+        let mut bootstrap = backup_bootstrap();
+        for (player, (transfers_in, transfers_out)) in bootstrap.elements.iter_mut().zip([(5, 0), (90, 7), (5, 40), (0, 0), (12, 3)]) {
+            player.extra.insert("transfers_in_event".to_string(), json!(transfers_in));
+            player.extra.insert("transfers_out_event".to_string(), json!(transfers_out));
+        }
+
+        let transfers = gameweek_transfers(&bootstrap, 1).unwrap();
+        let ids = |direction: &str| transfers[direction].as_array().unwrap().iter().map(|row| row["player_id"].clone()).collect::<Vec<_>>();
+        assert_eq!(ids("transfers_in"), vec![json!(2), json!(5), json!(1), json!(3), json!(4)]);
+        assert_eq!(ids("transfers_out"), vec![json!(3), json!(2), json!(5), json!(1), json!(4)]);
+        assert_eq!(transfers["transfers_in"][0], json!({"player_id": 2, "web_name": "Saliba", "team": 1, "transfers": 90}));
+
+        // Only the current gameweek has counts
+        assert_eq!(gameweek_transfers(&bootstrap, 2), None);
+    }
+
+    #[test]
+    fn ownership_history_skips_repeats_and_keeps_the_newest_points() {
+        let start = chrono::Utc::now();
+        let mut history = OwnershipHistory::default();
+        history.record(start, 10.0);
+        history.record(start, 10.0);
+        assert_eq!(history.points.len(), 1);
+
+        for minute in 0..=OWNERSHIP_HISTORY_MAX_POINTS as i64 {
+            history.record(start + chrono::Duration::minutes(minute), 11.0 + minute as f64);
+        }
+        assert_eq!(history.points.len(), OWNERSHIP_HISTORY_MAX_POINTS);
+        assert_eq!(history.points.front().unwrap().1, 12.0);
+        assert_eq!(history.to_json()[0]["selected_by_percent"], 12.0);
+    }
+
+    #[tokio::test]
+    async fn ownership_is_only_recorded_from_primary_data() {
+        let id = 90_001;
+        assert_eq!(record_ownership(id, Some(20.5), DataSource::Primary).await.points.len(), 1);
+
+        for source in [DataSource::LocalBackup, DataSource::BackupUrl] {
+            let history = record_ownership(id, Some(3.0), source).await;
+            assert_eq!(history.points.len(), 1);
+            assert_eq!(history.points[0].1, 20.5);
+        }
+        assert!(record_ownership(90_002, Some(3.0), DataSource::LocalBackup).await.points.is_empty());
+    }
+
+    #[test]
+    fn search_players_caps_results() {
+        let mut bootstrap = backup_bootstrap();
+        let template = bootstrap.elements[0].clone();
+        bootstrap.elements = (0..PLAYER_SEARCH_MAX as u32 + 5)
+            .map(|id| Player { id, ..template.clone() })
+            .collect();
+
+        assert_eq!(search_players(&bootstrap, "raya").len(), PLAYER_SEARCH_MAX);
+    }
+
+    #[test]
+    fn decode_query_value_handles_plus_and_percent() {
+        assert_eq!(decode_query_value("Bukayo+Saka"), "Bukayo Saka");
+        assert_eq!(decode_query_value("%C3%98degaard"), "Ødegaard");
+        assert_eq!(decode_query_value("100%"), "100%");
+    }
+
+    #[test]
+    fn requested_fields_splits_and_decodes_the_list() {
+        assert_eq!(requested_fields(Some("event=3")), None);
+        assert_eq!(requested_fields(Some("fields=")), None);
+        assert_eq!(
+            requested_fields(Some("fields=teams,%20events,,")),
+            Some(vec!["teams".to_string(), "events".to_string()])
+        );
+    }
+
+    #[tokio::test]
+    async fn cache_hits_keep_the_data_source() {
+        let policy = CachePolicy::new("source-test", 600);
+        let key = policy.cache_key(&[&1]);
+        let fetch = |source| async move {
+            Ok(FetchResult {
+                data: json!({"value": 1}),
+                last_modified: None,
+                source,
+            })
+        };
+
+        let fetched = get_cached_or_compute(&key, policy, fetch(DataSource::LocalBackup)).await.unwrap();
+        assert_eq!(fetched.source, DataSource::LocalBackup);
+        let cached = get_cached_or_compute(&key, policy, fetch(DataSource::Primary)).await.unwrap();
+        assert_eq!((cached.cache_status, cached.source), (CacheStatus::Hit, DataSource::LocalBackup));
+    }
+
+    #[test]
+    fn data_source_envelope_uses_snake_case_names() {
+        assert_eq!(
+            with_data_source(json!([1]), DataSource::BackupUrl),
+            json!({"data": [1], "data_source": "backup_url"})
+        );
+        // Joined payloads report the most degraded of their sources
+        assert_eq!(DataSource::Primary.max(DataSource::LocalBackup), DataSource::LocalBackup);
+    }
+
+    #[test]
+    fn project_fields_keeps_only_requested_keys() {
+        let data = json!({"teams": [], "events": [1], "elements": [2]});
+        let fields = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            project_fields(data.clone(), &fields(&["events", "teams"])),
+            json!({"teams": [], "events": [1]})
+        );
+        assert_eq!(project_fields(data, &fields(&["nope"])), json!({}));
+        assert_eq!(project_fields(json!([{"id": 1}]), &fields(&["id"])), json!([{"id": 1}]));
+    }
+
+    #[test]
+    fn jsonp_callback_accepts_only_identifiers() {
+        assert_eq!(jsonp_callback(Some("limit=5")).unwrap(), None);
+        assert_eq!(jsonp_callback(Some("callback=$_jsonp1")).unwrap().as_deref(), Some("$_jsonp1"));
+        let too_long = format!("callback={}", "a".repeat(MAX_JSONP_CALLBACK_LEN + 1));
+        for invalid in ["callback=", "callback=1cb", "callback=alert(1)", "callback=a.b", "callback=%3Cscript%3E", &too_long] {
+            assert!(matches!(jsonp_callback(Some(invalid)), Err(ProxyError::InvalidPathParam(_))), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn pretty_is_refused_outside_debug_mode() {
+        assert!(!pretty_requested(Some("limit=5"), false).unwrap());
+        assert!(!pretty_requested(Some("pretty=false"), false).unwrap());
+        assert!(pretty_requested(Some("pretty"), true).unwrap());
+        assert!(pretty_requested(Some("limit=5&pretty=true"), true).unwrap());
+        assert!(matches!(pretty_requested(Some("pretty"), false), Err(ProxyError::InvalidPathParam(_))));
+    }
+
+    #[test]
+    fn pretty_bodies_are_indented_with_four_spaces() {
+        let data = json!({"data": {"status": "OK"}, "data_source": "primary"});
+        let (content_type, body) = encode_body(&data, None, BodyFormat::Json, true).unwrap();
+        let body = String::from_utf8(body).unwrap();
+
+        assert_eq!(content_type, "application/json");
+        assert!(body.contains('\n'));
+        assert!(body.contains("\n    \"data\": {\n        \"status\": \"OK\"\n    }"), "{}", body);
+        assert_eq!(serde_json::from_str::<Value>(&body).unwrap(), data);
+    }
+
+    #[tokio::test]
+    async fn handler_wraps_jsonp_responses_without_cors_headers() {
+        let request = http::Request::builder().uri("/health?callback=onHealth").body(Body::Empty).unwrap();
+        let response = handler(request).await.unwrap();
+
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["content-type"], "application/javascript");
+        assert!(response.headers().get("access-control-allow-origin").is_none());
+        let Body::Binary(bytes) = response.body() else {
+            panic!("JSONP should be a binary body");
+        };
+        let body = std::str::from_utf8(bytes).unwrap();
+        assert!(body.starts_with("onHealth({") && body.ends_with("});"), "{}", body);
+
+        let request = http::Request::builder().uri("/health?callback=on-health").body(Body::Empty).unwrap();
+        assert_eq!(handler(request).await.unwrap().status(), 400);
+    }
+
+    #[test]
+    fn router_matches_every_route_pattern() {
+        let cases = [
+            ("/health", "/health"),
+            ("/status", "/status"),
+            ("/openapi.json", "/openapi.json"),
+            ("/cache/status", "/cache/status"),
+            ("/cache/teams", "/cache/:key"),
+            ("/bootstrap-static", "/bootstrap-static"),
+            ("/teams", "/teams"),
+            ("/team/1/players", "/team/:team_id/players"),
+            ("/team/1", "/team/*"),
+            ("/events", "/events"),
+            ("/events/current", "/events/current"),
+            ("/events/next", "/events/next"),
+            ("/element-types", "/element-types"),
+            ("/gameweek/current", "/gameweek/current"),
+            ("/gameweek/3/bonus", "/gameweek/:gw/bonus"),
+            ("/gameweek/3/top-performers", "/gameweek/:gw/top-performers"),
+            ("/gameweek/7/differentials", "/gameweek/:gw/differentials"),
+            ("/gameweek/7/captain-choices", "/gameweek/:gw/captain-choices"),
+            ("/gameweek/7/results", "/gameweek/:gw/results"),
+            ("/gameweek/7/transfers-in-out", "/gameweek/:gw/transfers-in-out"),
+            ("/gameweek/3", "/gameweek/*"),
+            ("/transfers", "/transfers"),
+            ("/season/history", "/season/history"),
+            ("/fixtures", "/fixtures"),
+            ("/fixtures/typed", "/fixtures/typed"),
+            ("/fixtures/upcoming", "/fixtures/upcoming"),
+            ("/fixtures/gameweek/3", "/fixtures/gameweek/:gw"),
+            ("/fixtures/gameweek/3/extra", "/fixtures/gameweek/*"),
+            ("/element-summary/bulk", "/element-summary/bulk"),
+            ("/element-summary/328", "/element-summary/:id"),
+            ("/element-summary/328/fixtures", "/element-summary/:id/fixtures"),
+            ("/element-summary/328/extra", "/element-summary/*"),
+            ("/live-event/12", "/live-event/:gw"),
+            ("/live-event/12/extra", "/live-event/*"),
+            ("/event/12/live", "/event/:gw/live"),
+            ("/event/12/live/", "/event/:gw/live/"),
+            ("/players", "/players"),
+            ("/player/search", "/player/search"),
+            ("/player/328/history", "/player/:id/history"),
+            ("/player/328/stats", "/player/:id/stats"),
+            ("/player/302/form-trend", "/player/:id/form-trend"),
+            ("/player/302/ownership-trend", "/player/:id/ownership-trend"),
+            ("/player/302/price-change-history", "/player/:id/price-change-history"),
+            ("/player/328/upcoming-fixtures", "/player/:id/upcoming-fixtures"),
+            ("/player/328/unknown", "/player/:id/:endpoint"),
+            ("/player/328", "/player/*"),
+            ("/dream-team/5", "/dream-team/:gw"),
+            ("/dream-team/5/extra", "/dream-team/*"),
+            ("/picks/123/4", "/picks/:manager_id/:gw"),
+            ("/picks/123", "/picks/*"),
+            ("/manager/123", "/manager/:id"),
+            ("/manager/123/transfers", "/manager/:id/transfers"),
+            ("/manager/123/transfers/latest", "/manager/:id/transfers/latest"),
+            ("/manager/123/history", "/manager/:id/history"),
+            ("/manager/123/squad", "/manager/:id/squad"),
+            ("/manager/123/cup", "/manager/:id/cup"),
+            ("/manager/123/event/4/automatic-subs", "/manager/:id/event/:gw/automatic-subs"),
+            ("/manager/123/points/4", "/manager/:id/points/:gw"),
+            ("/manager/123/team-value/4", "/manager/:id/team-value/:gw"),
+            ("/manager/123/season-summary", "/manager/:id/season-summary"),
+            ("/manager/123/mini-leagues", "/manager/:id/mini-leagues"),
+            ("/manager/123/unknown", "/manager/:id/:endpoint"),
+            ("/manager/123/event/4", "/manager/*"),
+            ("/chip-history/123", "/chip-history/:manager_id"),
+            ("/chip-history/123/extra", "/chip-history/*"),
+            ("/league/314/1", "/league/:league_id/:page"),
+            ("/league/mon/314/2", "/league/mon/:league_id/:phase"),
+            ("/league/314/standings/all", "/league/:league_id/standings/all"),
+            ("/league/314/top/10", "/league/:league_id/top/:n"),
+            ("/league/314/rank/42", "/league/:league_id/rank/:manager_id"),
+            ("/leagues-classic/314/h2h/1/2", "/leagues-classic/:league_id/h2h/:entry_a/:entry_b"),
+            ("/league/314", "/league/*"),
+            ("/leagues-classic/314/new-entries/1", "/leagues-classic/:league_id/new-entries/:page"),
+            ("/leagues-classic/314", "/leagues-classic/*"),
+            ("/leagues-h2h/99/1", "/leagues-h2h/:league_id/:page"),
+            ("/leagues-h2h/99", "/leagues-h2h/*"),
+            ("/h2h-league/99/matches", "/h2h-league/:id/matches"),
+            ("/h2h-league/99/matches/2", "/h2h-league/:id/matches/:page"),
+            ("/h2h-league/99/standings", "/h2h-league/*"),
+        ];
+
+        for (path, pattern) in cases {
+            assert_eq!(router().find(path), Some(pattern), "{}", path);
+        }
+        assert_eq!(router().find("/event/12/other"), None);
+        assert_eq!(router().find("/unknown"), None);
+    }
+
+    #[tokio::test]
+    async fn router_rejects_malformed_paths_under_known_prefixes() {
+        let dispatch = |uri: &'static str| async move {
+            let request = http::Request::builder().uri(uri).body(Body::Empty).unwrap();
+            router().dispatch(&request).await
+        };
+
+        assert!(matches!(dispatch("/picks/123").await, Err(ProxyError::InvalidPathParam(what)) if what == "picks path"));
+        assert!(matches!(dispatch("/manager/123/unknown").await, Err(ProxyError::InvalidPathParam(what)) if what == "manager endpoint"));
+        assert!(matches!(dispatch("/event/12/other").await, Err(ProxyError::UnknownRoute)));
+        assert_eq!(dispatch("/health").await.unwrap().data["status"], "OK");
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn mock_feature_serves_backup_data_without_network() {
+        let fetched = fetch_with_fallback("http://127.0.0.1:9/unreachable", None, Some("bootstrap-static"))
+            .await
+            .unwrap();
+        assert_eq!(fetched.data, load_backup_data("bootstrap-static").unwrap());
+
+        let missing = fetch_with_fallback("http://127.0.0.1:9/unreachable", None, None).await;
+        assert!(matches!(missing, Err(ProxyError::UpstreamUnavailable)));
+    }
+
+    #[test]
+    fn season_summary_aggregates_every_gameweek() {
+        let history = json!({
+            "current": [
+                {"event": 1, "points": 70, "total_points": 70, "overall_rank": 500_000, "event_transfers": 0, "event_transfers_cost": 0},
+                {"event": 2, "points": 41, "total_points": 111, "overall_rank": 900_000, "event_transfers": 2, "event_transfers_cost": 4},
+                {"event": 3, "points": 70, "total_points": 181, "overall_rank": 400_000, "event_transfers": 1, "event_transfers_cost": 0}
+            ]
+        });
+
+        let summary = season_summary(&history);
+        assert_eq!(summary["gameweeks_played"], 3);
+        assert_eq!(summary["total_points"], 181);
+        assert_eq!((summary["best_gw"].clone(), summary["best_gw_points"].clone()), (json!(1), json!(70)));
+        assert_eq!((summary["worst_gw"].clone(), summary["worst_gw_points"].clone()), (json!(2), json!(41)));
+        assert!((summary["average_points"].as_f64().unwrap() - 181.0 / 3.0).abs() < 1e-9);
+        assert_eq!(summary["total_transfers"], 3);
+        assert_eq!(summary["total_transfer_cost"], 4);
+        assert_eq!(summary["overall_rank_history"][1], json!({"gw": 2, "overall_rank": 900_000}));
+    }
+
+    #[test]
+    fn season_summary_of_empty_history_has_no_extremes() {
+        let summary = season_summary(&json!({"current": []}));
+        assert_eq!(summary["gameweeks_played"], 0);
+        assert!(summary["best_gw"].is_null() && summary["average_points"].is_null());
+        assert_eq!(summary["overall_rank_history"], json!([]));
+    }
+
+    #[test]
+    fn chip_history_labels_each_played_chip() {
+        let history = json!({
+            "current": [],
+            "chips": [
+                {"name": "wildcard", "time": "2025-09-12T17:20:00Z", "event": 4},
+                {"name": "3xc", "time": "2025-12-20T09:00:00Z", "event": 17},
+                {"name": "new-chip", "time": "2026-01-03T09:00:00Z", "event": 20}
+            ]
+        });
+
+        assert_eq!(
+            chip_history(&history),
+            json!([
+                {"name": "wildcard", "label": "Wildcard", "gameweek": 4, "played_at": "2025-09-12T17:20:00Z"},
+                {"name": "3xc", "label": "Triple Captain", "gameweek": 17, "played_at": "2025-12-20T09:00:00Z"},
+                {"name": "new-chip", "label": null, "gameweek": 20, "played_at": "2026-01-03T09:00:00Z"}
+            ])
+        );
+        assert_eq!(chip_history(&json!({"current": []})), json!([]));
+    }
+
+    fn squad(picks: Value) -> EntryPicks {
+        squad_with_chip(Value::Null, picks)
+    }
+
+    fn squad_with_chip(chip: Value, picks: Value) -> EntryPicks {
+        serde_json::from_value(json!({ "active_chip": chip, "picks": picks })).unwrap()
+    }
+
+    fn live(elements: &[(u32, u32, i32)]) -> LiveEvent {
+        let elements: Vec<Value> = elements
+            .iter()
+            .map(|(id, minutes, points)| json!({"id": id, "stats": {"minutes": minutes, "total_points": points}}))
+            .collect();
+        serde_json::from_value(json!({ "elements": elements })).unwrap()
+    }
+
+    fn pick(element: u32, multiplier: u32, is_captain: bool, is_vice_captain: bool) -> Value {
+        json!({
+            "element": element,
+            "position": element,
+            "multiplier": multiplier,
+            "is_captain": is_captain,
+            "is_vice_captain": is_vice_captain
+        })
+    }
+
+    #[test]
+    fn gameweek_points_apply_captain_multiplier() {
+        let picks = squad(json!([pick(1, 2, true, false), pick(2, 1, false, true), pick(3, 0, false, false)]));
+        let live = live(&[(1, 90, 10), (2, 90, 6), (3, 90, 8)]);
+
+        assert_eq!(gameweek_points(&picks, &live), (26, false));
+    }
+
+    #[test]
+    fn gameweek_points_fall_back_to_vice_captain() {
+        let picks = squad(json!([pick(1, 3, true, false), pick(2, 1, false, true)]));
+        let live = live(&[(1, 0, 0), (2, 90, 5)]);
+
+        assert_eq!(gameweek_points(&picks, &live), (15, false));
+    }
+
+    #[test]
+    fn gameweek_points_bench_boost_scores_the_bench() {
+        // Bench multipliers left at 0, as before upstream applies the chip
+        let picks = squad_with_chip(json!("bboost"), json!([pick(1, 2, true, false), pick(2, 1, false, true), pick(3, 0, false, false)]));
+        let live = live(&[(1, 90, 10), (2, 90, 6), (3, 90, 8)]);
+
+        assert_eq!(gameweek_points(&picks, &live), (34, false));
+    }
+
+    #[test]
+    fn gameweek_points_triple_captain_triples_the_captain() {
+        let picks = squad_with_chip(json!("3xc"), json!([pick(1, 2, true, false), pick(2, 1, false, true)]));
+        let full_squad = live(&[(1, 90, 10), (2, 90, 6)]);
+        assert_eq!(gameweek_points(&picks, &full_squad), (36, false));
+
+        // ...or the vice-captain when the captain did not play
+        let without_captain = live(&[(1, 0, 0), (2, 90, 6)]);
+        assert_eq!(gameweek_points(&picks, &without_captain), (18, false));
+    }
+
+    #[test]
+    fn team_value_sums_current_prices_and_bank() {
+        let bootstrap = backup_bootstrap();
+        let mut picks = squad(json!([pick(1, 1, false, false), pick(4, 2, true, false), pick(999, 0, false, false)]));
+        assert_eq!(team_value(&picks, &bootstrap), (155, 0));
+
+        picks.extra.insert("entry_history".to_string(), json!({"bank": 15, "value": 1000}));
+        assert_eq!(team_value(&picks, &bootstrap), (155, 15));
+    }
+
+    #[test]
+    fn gameweek_points_flag_missing_live_data() {
+        let picks = squad(json!([pick(1, 2, true, false), pick(2, 1, false, true)]));
+        let live = live(&[(1, 90, -1)]);
+
+        assert_eq!(gameweek_points(&picks, &live), (-2, true));
+    }
+
+    #[test]
+    fn upcoming_fixtures_from_backup_skip_played_and_sort_unscheduled_last() {
+        let Some(Value::Array(fixtures)) = load_backup_data("fixtures") else {
+            panic!("fixtures backup should be an array");
+        };
+
+        let ids: Vec<u64> = upcoming_fixtures(fixtures).iter().map(|fixture| fixture["id"].as_u64().unwrap()).collect();
+        assert_eq!(ids, vec![11, 28, 21]);
+    }
+
+    #[test]
+    fn upcoming_fixtures_exclude_started_matches() {
+        let fixtures = vec![
+            json!({"id": 1, "finished": false, "started": true, "kickoff_time": "2025-08-23T11:30:00Z"}),
+            json!({"id": 2, "finished": false, "started": false, "kickoff_time": null}),
+            json!({"id": 3, "finished": false, "started": false, "kickoff_time": "2025-08-24T13:00:00Z"}),
+            json!({"id": 4, "finished": false, "started": false, "kickoff_time": "2025-08-23T14:00:00Z"}),
+        ];
+
+        let ids: Vec<u64> = upcoming_fixtures(fixtures).iter().map(|fixture| fixture["id"].as_u64().unwrap()).collect();
+        assert_eq!(ids, vec![4, 3, 2]);
+    }
+
+    #[test]
+    fn team_upcoming_fixtures_from_backup_are_annotated_for_the_team() {
+        let Some(Value::Array(fixtures)) = load_backup_data("fixtures") else {
+            panic!("fixtures backup should be an array");
+        };
+
+        let annotated: Vec<(u64, bool, u64)> = team_upcoming_fixtures(fixtures, 1)
+            .iter()
+            .map(|fixture| {
+                (
+                    fixture["id"].as_u64().unwrap(),
+                    fixture["is_home"].as_bool().unwrap(),
+                    fixture["difficulty"].as_u64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(annotated, vec![(11, true, 2), (28, true, 3), (21, false, 5)]);
+    }
+
+    #[test]
+    fn team_upcoming_fixtures_keep_started_matches_of_the_team_only() {
+        let fixtures = vec![
+            json!({"id": 1, "team_h": 7, "team_a": 8, "finished": false, "kickoff_time": "2025-08-23T11:30:00Z"}),
+            json!({"id": 2, "team_h": 3, "team_a": 7, "finished": false, "started": true, "kickoff_time": "2025-08-16T14:00:00Z"}),
+            json!({"id": 3, "team_h": 7, "team_a": 3, "finished": true, "kickoff_time": "2025-08-09T14:00:00Z"}),
+            json!({"id": 4, "team_h": 1, "team_a": 2, "finished": false, "kickoff_time": "2025-08-16T14:00:00Z"}),
+        ];
+
+        let ids: Vec<u64> = team_upcoming_fixtures(fixtures, 7).iter().map(|fixture| fixture["id"].as_u64().unwrap()).collect();
+        assert_eq!(ids, vec![2, 1]);
+    }
+
+    #[test]
+    fn element_types_from_backup_bootstrap_cover_every_position() {
+        let types = array_field(&load_backup_data("bootstrap-static").unwrap(), "element_types");
+        let short_names: Vec<&str> = types
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|position| position["singular_name_short"].as_str())
+            .collect();
+        assert_eq!(short_names, vec!["GKP", "DEF", "MID", "FWD"]);
+    }
+
+    #[test]
+    fn teams_from_backup_bootstrap_has_every_club() {
+        let teams = array_field(&load_backup_data("bootstrap-static").unwrap(), "teams");
+        assert_eq!(teams.as_array().map(Vec::len), Some(20));
+    }
 }
@@ -0,0 +1,235 @@
+//! Path-pattern router for the proxy's JSON endpoints.
+//!
+//! Patterns are matched segment by segment in registration order: literal
+//! segments must match exactly, `:name` captures one segment and a trailing
+//! `*` matches one or more remaining segments. The first matching route wins,
+//! so specific routes are registered before the catch-alls that reject
+//! malformed paths under the same prefix. Paths no route matches are
+//! `404 Not Found`, listing the [`Router::patterns`] that do exist.
+
+use super::{forwarded_query, Payload, ProxyError};
+use std::{future::Future, pin::Pin};
+use tracing::Span;
+use vercel_runtime::Request;
+
+pub(crate) type BoxedFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+pub(crate) type BoxedHandler =
+    Box<dyn for<'a> Fn(RouteContext<'a>) -> BoxedFuture<'a, Result<Payload, ProxyError>> + Send + Sync>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Param(String),
+    Rest,
+}
+
+struct Route {
+    pattern: String,
+    segments: Vec<Segment>,
+    handler: BoxedHandler,
+}
+
+impl Route {
+    // Captured `:name` values, or None when `path` does not match
+    fn matches<'a>(&'a self, path: &'a str) -> Option<Vec<(&'a str, &'a str)>> {
+        let mut parts = path.split('/');
+        let mut params = Vec::new();
+
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => {
+                    if parts.next()? != literal {
+                        return None;
+                    }
+                }
+                Segment::Param(name) => params.push((name.as_str(), parts.next()?)),
+                Segment::Rest => {
+                    parts.next()?;
+                    return Some(params);
+                }
+            }
+        }
+
+        parts.next().is_none().then_some(params)
+    }
+}
+
+/// What a route handler gets to see of the request.
+pub(crate) struct RouteContext<'a> {
+    pub(crate) request: &'a Request,
+    params: Vec<(&'a str, &'a str)>,
+    forwarded: Option<String>,
+}
+
+impl<'a> RouteContext<'a> {
+    pub(crate) fn path(&self) -> &'a str {
+        self.request.uri().path()
+    }
+
+    /// Value captured by `:name` in the route pattern.
+    pub(crate) fn param(&self, name: &str) -> &'a str {
+        self.params
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map_or("", |(_, value)| *value)
+    }
+
+    /// The client's query string, including proxy-only parameters.
+    pub(crate) fn raw_query(&self) -> Option<&'a str> {
+        self.request.uri().query()
+    }
+
+    /// The query string forwarded upstream and used in cache keys.
+    pub(crate) fn query(&self) -> Option<&str> {
+        self.forwarded.as_deref()
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Router {
+    routes: Vec<Route>,
+}
+
+impl Router {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn add_route<F>(&mut self, pattern: &str, handler: F)
+    where
+        F: for<'a> Fn(RouteContext<'a>) -> BoxedFuture<'a, Result<Payload, ProxyError>> + Send + Sync + 'static,
+    {
+        let segments = pattern
+            .split('/')
+            .map(|segment| match segment {
+                "*" => Segment::Rest,
+                param if param.starts_with(':') => Segment::Param(param[1..].to_string()),
+                literal => Segment::Literal(literal.to_string()),
+            })
+            .collect();
+
+        self.routes.push(Route {
+            pattern: pattern.to_string(),
+            segments,
+            handler: Box::new(handler),
+        });
+    }
+
+    /// Registered patterns in registration order, without the `*` catch-alls,
+    /// which only exist to reject malformed paths.
+    pub(crate) fn patterns(&self) -> Vec<&str> {
+        self.routes
+            .iter()
+            .filter(|route| !route.segments.contains(&Segment::Rest))
+            .map(|route| route.pattern.as_str())
+            .collect()
+    }
+
+    /// Pattern of the route that `path` dispatches to.
+    pub(crate) fn find(&self, path: &str) -> Option<&str> {
+        self.routes
+            .iter()
+            .find(|route| route.matches(path).is_some())
+            .map(|route| route.pattern.as_str())
+    }
+
+    pub(crate) fn dispatch<'a>(&'a self, request: &'a Request) -> BoxedFuture<'a, Result<Payload, ProxyError>> {
+        let path = request.uri().path();
+        let matched = self
+            .routes
+            .iter()
+            .find_map(|route| route.matches(path).map(|params| (route, params)));
+
+        match matched {
+            Some((route, params)) => {
+                // Low-cardinality label for the request log line
+                Span::current().record("route", route.pattern.as_str());
+                (route.handler)(RouteContext {
+                    request,
+                    params,
+                    forwarded: forwarded_query(request.uri().query()),
+                })
+            }
+            None => Box::pin(async { Err(ProxyError::UnknownRoute) }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use vercel_runtime::Body;
+
+    fn echo_router(patterns: &[&'static str]) -> Router {
+        let mut router = Router::new();
+        for &pattern in patterns {
+            router.add_route(pattern, move |_: RouteContext<'_>| {
+                Box::pin(async move { Ok(Payload::from(json!({ "route": pattern }))) })
+            });
+        }
+        router
+    }
+
+    fn request(uri: &str) -> Request {
+        http::Request::builder().uri(uri).body(Body::Empty).unwrap()
+    }
+
+    #[test]
+    fn literal_and_param_segments_must_all_match() {
+        let router = echo_router(&["/picks/:manager_id/:gw"]);
+        assert_eq!(router.find("/picks/1/2"), Some("/picks/:manager_id/:gw"));
+        assert_eq!(router.find("/picks/1"), None);
+        assert_eq!(router.find("/picks/1/2/3"), None);
+        assert_eq!(router.find("/pick/1/2"), None);
+    }
+
+    #[test]
+    fn rest_segment_needs_at_least_one_segment() {
+        let router = echo_router(&["/picks/*"]);
+        assert_eq!(router.find("/picks/1/2/3"), Some("/picks/*"));
+        assert_eq!(router.find("/picks/"), Some("/picks/*"));
+        assert_eq!(router.find("/picks"), None);
+    }
+
+    #[test]
+    fn first_registered_route_wins() {
+        let router = echo_router(&["/element-summary/bulk", "/element-summary/:id"]);
+        assert_eq!(router.find("/element-summary/bulk"), Some("/element-summary/bulk"));
+        assert_eq!(router.find("/element-summary/7"), Some("/element-summary/:id"));
+    }
+
+    #[tokio::test]
+    async fn dispatch_passes_params_and_strips_proxy_query() {
+        let mut router = Router::new();
+        router.add_route("/picks/:manager_id/:gw", |ctx: RouteContext<'_>| {
+            let data = json!({
+                "manager_id": ctx.param("manager_id"),
+                "gw": ctx.param("gw"),
+                "query": ctx.query(),
+                "raw_query": ctx.raw_query()
+            });
+            Box::pin(async move { Ok(Payload::from(data)) })
+        });
+
+        let request = request("/picks/12/3?limit=5&event=3");
+        let payload = router.dispatch(&request).await.unwrap();
+        assert_eq!(
+            payload.data,
+            json!({"manager_id": "12", "gw": "3", "query": "event=3", "raw_query": "limit=5&event=3"})
+        );
+    }
+
+    #[tokio::test]
+    async fn dispatch_without_matching_route_is_not_found() {
+        let router = echo_router(&["/health"]);
+        let request = request("/nope");
+        assert!(matches!(router.dispatch(&request).await, Err(ProxyError::UnknownRoute)));
+    }
+
+    #[test]
+    fn patterns_skip_catch_alls() {
+        let router = echo_router(&["/picks/:manager_id/:gw", "/picks/*", "/health"]);
+        assert_eq!(router.patterns(), vec!["/picks/:manager_id/:gw", "/health"]);
+    }
+}
@@ -0,0 +1,73 @@
+//! Benchmarks for the hot paths of the proxy handler.
+//!
+//! Run with `cargo bench --bench proxy`. The handler is a Vercel binary, so its
+//! private functions are compiled into this bench through a `#[path]` module.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+#[allow(dead_code, unused_imports)]
+#[path = "../api/handler.rs"]
+mod handler;
+
+use handler::{compress_if_accepted, compute_etag, get_cached_or_fetch, load_backup_data, CachePolicy};
+
+fn bench_load_backup_data(c: &mut Criterion) {
+    c.bench_function("load_backup_data/bootstrap-static", |b| {
+        b.iter(|| load_backup_data(black_box("bootstrap-static")))
+    });
+}
+
+fn bench_cache(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().expect("tokio runtime");
+    let server = runtime.block_on(async {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/bench/"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"events": [], "teams": []})))
+            .mount(&server)
+            .await;
+        server
+    });
+    let url = format!("{}/bench/", server.uri());
+    let policy = CachePolicy::new("bench", 600);
+
+    // Warm one key so every iteration of the hit benchmark is served from cache
+    runtime.block_on(get_cached_or_fetch("bench-hit", policy, &url, None, None)).expect("warm-up fetch");
+    c.bench_function("get_cached_or_fetch/hit", |b| {
+        b.to_async(&runtime)
+            .iter(|| async { get_cached_or_fetch("bench-hit", policy, &url, None, None).await.unwrap() })
+    });
+
+    // A fresh key per iteration forces a round trip to the mock upstream
+    let next_key = AtomicU64::new(0);
+    c.bench_function("get_cached_or_fetch/miss", |b| {
+        b.to_async(&runtime).iter(|| async {
+            let key = format!("bench-miss-{}", next_key.fetch_add(1, Ordering::Relaxed));
+            get_cached_or_fetch(&key, policy, &url, None, None).await.unwrap()
+        })
+    });
+}
+
+fn bench_serialization(c: &mut Criterion) {
+    let data: Value = load_backup_data("bootstrap-static").expect("bootstrap-static backup");
+    let mut accepts_gzip = http::HeaderMap::new();
+    accepts_gzip.insert("accept-encoding", "gzip".parse().unwrap());
+
+    c.bench_function("serialize/bootstrap-static", |b| {
+        b.iter(|| {
+            let body = serde_json::to_string(black_box(&data)).unwrap();
+            let etag = compute_etag(black_box(&data));
+            let (compressed, _) = compress_if_accepted(&accepts_gzip, body.into());
+            (etag, compressed)
+        })
+    });
+}
+
+criterion_group!(benches, bench_load_backup_data, bench_cache, bench_serialization);
+criterion_main!(benches);
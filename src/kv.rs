@@ -0,0 +1,76 @@
+//! Redis-backed cache layer shared by every serverless instance.
+//!
+//! Values are opaque strings; the handler decides how entries are encoded.
+//! Every key is namespaced with [`KEY_PREFIX`] so the proxy can share a Redis
+//! database with other applications.
+
+use redis::{aio::MultiplexedConnection, AsyncCommands, Client, RedisResult};
+use std::time::Duration;
+
+/// Prefix prepended to every key written by the proxy.
+pub const KEY_PREFIX: &str = "fpl-proxy:";
+
+/// Async Redis cache using a single multiplexed connection.
+#[derive(Clone)]
+pub struct KvCache {
+    connection: MultiplexedConnection,
+}
+
+impl KvCache {
+    /// Connects to the Redis server at `url`, e.g. `redis://default:<password>@host:6379`
+    /// or `rediss://...` for TLS.
+    pub async fn connect(url: &str) -> RedisResult<Self> {
+        let client = Client::open(url)?;
+        let connection = client.get_multiplexed_tokio_connection().await?;
+        Ok(Self { connection })
+    }
+
+    /// Returns the value stored under `key` together with its remaining TTL.
+    pub async fn get(&self, key: &str) -> RedisResult<Option<(String, Duration)>> {
+        let mut connection = self.connection.clone();
+        let (value, ttl_ms): (Option<String>, i64) = redis::pipe()
+            .get(namespaced(key))
+            .pttl(namespaced(key))
+            .query_async(&mut connection)
+            .await?;
+
+        // PTTL is negative for missing keys and keys without an expiry
+        Ok(value
+            .filter(|_| ttl_ms > 0)
+            .map(|value| (value, Duration::from_millis(ttl_ms as u64))))
+    }
+
+    /// Stores `value` under `key`, expiring after `ttl`.
+    pub async fn set(&self, key: &str, value: &str, ttl: Duration) -> RedisResult<()> {
+        let mut connection = self.connection.clone();
+        let ttl_ms = ttl.as_millis().max(1) as u64;
+        connection.pset_ex(namespaced(key), value, ttl_ms).await
+    }
+
+    /// Removes `key`, returning whether it existed.
+    pub async fn delete(&self, key: &str) -> RedisResult<bool> {
+        let mut connection = self.connection.clone();
+        let removed: u64 = connection.del(namespaced(key)).await?;
+        Ok(removed > 0)
+    }
+}
+
+fn namespaced(key: &str) -> String {
+    format!("{}{}", KEY_PREFIX, key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_namespaced() {
+        assert_eq!(namespaced("live-event-12"), "fpl-proxy:live-event-12");
+    }
+
+    #[test]
+    fn connect_rejects_malformed_urls() {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        assert!(runtime.block_on(KvCache::connect("not a redis url")).is_err());
+    }
+}
@@ -0,0 +1,6 @@
+//! Shared types for the Fantasy PL proxy handler.
+
+pub mod kv;
+pub mod metrics;
+pub mod model;
+pub mod tls;
@@ -0,0 +1,101 @@
+//! Prometheus metrics for the proxy, rendered in the text exposition format.
+//!
+//! Requests are labelled with the route pattern they matched, e.g.
+//! `/picks/:manager_id/:gw`, never the raw path, so the number of series stays
+//! bounded whatever paths clients send.
+
+use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+static REGISTRY: OnceLock<Registry> = OnceLock::new();
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+struct Metrics {
+    requests_total: IntCounterVec,
+    upstream_duration_seconds: HistogramVec,
+    cache_operations_total: IntCounterVec,
+}
+
+/// Registry holding every proxy metric.
+pub fn registry() -> &'static Registry {
+    REGISTRY.get_or_init(Registry::new)
+}
+
+fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(|| {
+        let requests_total = IntCounterVec::new(
+            Opts::new("proxy_requests_total", "Requests handled, by route pattern and status"),
+            &["path", "status"],
+        )
+        .expect("valid requests_total metric");
+        let upstream_duration_seconds = HistogramVec::new(
+            HistogramOpts::new("proxy_upstream_duration_seconds", "Upstream request latency, by source"),
+            &["source"],
+        )
+        .expect("valid upstream_duration_seconds metric");
+        let cache_operations_total = IntCounterVec::new(
+            Opts::new("proxy_cache_operations_total", "Cache operations, by operation"),
+            &["operation"],
+        )
+        .expect("valid cache_operations_total metric");
+
+        let registry = registry();
+        registry.register(Box::new(requests_total.clone())).expect("register requests_total");
+        registry
+            .register(Box::new(upstream_duration_seconds.clone()))
+            .expect("register upstream_duration_seconds");
+        registry
+            .register(Box::new(cache_operations_total.clone()))
+            .expect("register cache_operations_total");
+
+        Metrics {
+            requests_total,
+            upstream_duration_seconds,
+            cache_operations_total,
+        }
+    })
+}
+
+/// Counts a handled request under `route`, a fixed label such as a route pattern.
+pub fn record_request(route: &str, status: u16) {
+    metrics()
+        .requests_total
+        .with_label_values(&[route, &status.to_string()])
+        .inc();
+}
+
+/// Records how long a request to an upstream source (`primary`, `backup`) took.
+pub fn observe_upstream(source: &str, elapsed: Duration) {
+    metrics()
+        .upstream_duration_seconds
+        .with_label_values(&[source])
+        .observe(elapsed.as_secs_f64());
+}
+
+/// Counts a cache operation such as `hit`, `miss` or `store`.
+pub fn record_cache_operation(operation: &str) {
+    metrics().cache_operations_total.with_label_values(&[operation]).inc();
+}
+
+/// Every registered metric in the Prometheus text format.
+pub fn render() -> Result<String, prometheus::Error> {
+    // Make sure the metrics exist even before the first observation
+    metrics();
+    TextEncoder::new().encode_to_string(&registry().gather())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_includes_recorded_series() {
+        record_cache_operation("hit");
+        observe_upstream("primary", Duration::from_millis(120));
+
+        let text = render().unwrap();
+        assert!(text.contains("proxy_cache_operations_total{operation=\"hit\"}"));
+        assert!(text.contains("proxy_upstream_duration_seconds_count{source=\"primary\"}"));
+    }
+}
@@ -0,0 +1,459 @@
+//! Typed views of Fantasy Premier League API payloads.
+//!
+//! Only the fields the proxy relies on are typed; everything else is kept in
+//! `extra` so re-serializing a payload returns exactly what upstream sent.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Response of `GET /api/bootstrap-static/`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BootstrapStatic {
+    pub events: Vec<Event>,
+    pub teams: Vec<Team>,
+    pub elements: Vec<Player>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl BootstrapStatic {
+    /// The gameweek flagged `is_current`, or the `is_next` one before the season starts.
+    pub fn current_event(&self) -> Option<&Event> {
+        self.events
+            .iter()
+            .find(|event| event.is_current)
+            .or_else(|| self.events.iter().find(|event| event.is_next))
+    }
+}
+
+/// A gameweek.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    pub id: u32,
+    pub name: String,
+    pub deadline_time: Option<String>,
+    pub finished: bool,
+    pub is_previous: bool,
+    pub is_current: bool,
+    pub is_next: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A Premier League club.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Team {
+    pub id: u32,
+    pub code: u32,
+    pub name: String,
+    pub short_name: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A player (an "element" in FPL terms).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Player {
+    pub id: u32,
+    pub web_name: String,
+    pub first_name: String,
+    pub second_name: String,
+    pub team: u32,
+    pub element_type: u32,
+    pub now_cost: u32,
+    pub selected_by_percent: String,
+    pub total_points: i32,
+    pub status: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A Premier League match, as returned by `GET /api/fixtures/`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fixture {
+    pub id: u32,
+    /// Gameweek of the fixture; `None` while it is unscheduled.
+    pub event: Option<u32>,
+    pub team_h: u32,
+    pub team_a: u32,
+    pub team_h_score: Option<u32>,
+    pub team_a_score: Option<u32>,
+    pub finished: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Response of `GET /api/event/:gw/live/`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LiveEvent {
+    pub elements: Vec<LiveElementStats>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl LiveEvent {
+    /// Live stats of the player with the given element ID.
+    pub fn stats(&self, element: u32) -> Option<&PlayerLiveStats> {
+        self.elements.iter().find(|live| live.id == element).map(|live| &live.stats)
+    }
+}
+
+/// Live gameweek numbers of one player.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LiveElementStats {
+    pub id: u32,
+    pub stats: PlayerLiveStats,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Gameweek totals of a player; a stat is `None` when upstream omits it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlayerLiveStats {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minutes: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goals_scored: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assists: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clean_sheets: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goals_conceded: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub own_goals: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub penalties_saved: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub penalties_missed: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub yellow_cards: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub red_cards: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub saves: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bonus: Option<u32>,
+    /// Bonus points system score; negative for poor performances.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bps: Option<i32>,
+    /// FPL points for the gameweek; negative after cards or own goals.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_points: Option<i32>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Response of `GET /api/entry/:id/event/:gw/picks/`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntryPicks {
+    /// Chip played this gameweek, e.g. `3xc` or `bboost`.
+    pub active_chip: Option<String>,
+    pub picks: Vec<Pick>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl EntryPicks {
+    /// The chip played this gameweek, if it is one the proxy knows.
+    pub fn chip(&self) -> Option<Chip> {
+        self.active_chip.as_deref().and_then(Chip::from_code)
+    }
+}
+
+/// A chip a manager can play once (or twice) per season.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chip {
+    Wildcard,
+    FreeHit,
+    /// Bench players score as well as the starting XI.
+    BenchBoost,
+    /// The captain scores three times their points instead of twice.
+    TripleCaptain,
+    AssistantManager,
+}
+
+impl Chip {
+    /// Parses an FPL chip code such as `bboost` or `3xc`.
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "wildcard" => Some(Chip::Wildcard),
+            "freehit" => Some(Chip::FreeHit),
+            "bboost" => Some(Chip::BenchBoost),
+            "3xc" => Some(Chip::TripleCaptain),
+            "manager" => Some(Chip::AssistantManager),
+            _ => None,
+        }
+    }
+
+    /// Display name, e.g. "Bench Boost".
+    pub fn label(self) -> &'static str {
+        match self {
+            Chip::Wildcard => "Wildcard",
+            Chip::FreeHit => "Free Hit",
+            Chip::BenchBoost => "Bench Boost",
+            Chip::TripleCaptain => "Triple Captain",
+            Chip::AssistantManager => "Assistant Manager",
+        }
+    }
+}
+
+/// One of the 15 players of a manager's squad.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pick {
+    pub element: u32,
+    /// 1-11 for the starting XI, 12-15 for the bench.
+    pub position: u32,
+    /// 0 on the bench, 2 for the captain, 3 with Triple Captain.
+    pub multiplier: u32,
+    pub is_captain: bool,
+    pub is_vice_captain: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Result of a fixture from the home side's point of view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchOutcome {
+    Home,
+    Away,
+    Draw,
+    /// Not finished yet, including fixtures that have not kicked off.
+    InProgress,
+}
+
+impl Fixture {
+    pub fn outcome(&self) -> MatchOutcome {
+        match (self.finished, self.team_h_score, self.team_a_score) {
+            (true, Some(home), Some(away)) if home > away => MatchOutcome::Home,
+            (true, Some(home), Some(away)) if home < away => MatchOutcome::Away,
+            (true, Some(_), Some(_)) => MatchOutcome::Draw,
+            _ => MatchOutcome::InProgress,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn bootstrap_static_backup_round_trips() {
+        let raw: Value = serde_json::from_str(include_str!("../backup-data/bootstrap-static.json")).unwrap();
+
+        let typed: BootstrapStatic = serde_json::from_value(raw.clone()).unwrap();
+
+        assert_eq!(typed.events[0].name, "Gameweek 1");
+        assert!(typed.events[0].is_current);
+        assert_eq!(typed.teams[0].short_name, "ARS");
+        assert_eq!(serde_json::to_value(&typed).unwrap(), raw);
+    }
+
+    #[test]
+    fn player_snapshot_matches_struct() {
+        let raw = json!({
+            "id": 328,
+            "web_name": "M.Salah",
+            "first_name": "Mohamed",
+            "second_name": "Salah",
+            "team": 12,
+            "element_type": 3,
+            "now_cost": 130,
+            "selected_by_percent": "62.1",
+            "total_points": 344,
+            "status": "a",
+            "form": "9.5",
+            "goals_scored": 29
+        });
+
+        let player: Player = serde_json::from_value(raw.clone()).unwrap();
+
+        assert_eq!(player.web_name, "M.Salah");
+        assert_eq!(player.now_cost, 130);
+        assert_eq!(player.extra["goals_scored"], 29);
+        assert_eq!(serde_json::to_value(&player).unwrap(), raw);
+    }
+
+    fn event(id: u32, is_current: bool, is_next: bool) -> Event {
+        Event {
+            id,
+            name: format!("Gameweek {}", id),
+            deadline_time: Some(format!("2025-01-{:02}T11:00:00Z", id)),
+            finished: false,
+            is_previous: false,
+            is_current,
+            is_next,
+            extra: Map::new(),
+        }
+    }
+
+    fn bootstrap_with(events: Vec<Event>) -> BootstrapStatic {
+        BootstrapStatic {
+            events,
+            teams: Vec::new(),
+            elements: Vec::new(),
+            extra: Map::new(),
+        }
+    }
+
+    #[test]
+    fn current_event_prefers_is_current() {
+        let bootstrap = bootstrap_with(vec![event(23, false, false), event(24, true, false), event(25, false, true)]);
+        assert_eq!(bootstrap.current_event().map(|event| event.id), Some(24));
+    }
+
+    #[test]
+    fn current_event_falls_back_to_is_next() {
+        let bootstrap = bootstrap_with(vec![event(1, false, true), event(2, false, false)]);
+        assert_eq!(bootstrap.current_event().map(|event| event.id), Some(1));
+
+        assert!(bootstrap_with(vec![event(38, false, false)]).current_event().is_none());
+    }
+
+    fn fixture(finished: bool, home: Option<u32>, away: Option<u32>) -> Fixture {
+        Fixture {
+            id: 1,
+            event: Some(1),
+            team_h: 1,
+            team_a: 2,
+            team_h_score: home,
+            team_a_score: away,
+            finished,
+            extra: Map::new(),
+        }
+    }
+
+    #[test]
+    fn fixture_outcome_follows_score() {
+        assert_eq!(fixture(true, Some(2), Some(1)).outcome(), MatchOutcome::Home);
+        assert_eq!(fixture(true, Some(0), Some(3)).outcome(), MatchOutcome::Away);
+        assert_eq!(fixture(true, Some(1), Some(1)).outcome(), MatchOutcome::Draw);
+        assert_eq!(fixture(false, Some(1), Some(0)).outcome(), MatchOutcome::InProgress);
+        assert_eq!(fixture(false, None, None).outcome(), MatchOutcome::InProgress);
+    }
+
+    #[test]
+    fn fixture_snapshot_matches_struct() {
+        let raw = json!({
+            "id": 10,
+            "event": null,
+            "team_h": 14,
+            "team_a": 6,
+            "team_h_score": null,
+            "team_a_score": null,
+            "finished": false,
+            "kickoff_time": null,
+            "team_h_difficulty": 3
+        });
+
+        let fixture: Fixture = serde_json::from_value(raw.clone()).unwrap();
+
+        assert_eq!(fixture.event, None);
+        assert_eq!(fixture.extra["team_h_difficulty"], 3);
+        assert_eq!(serde_json::to_value(&fixture).unwrap(), raw);
+        assert_eq!(serde_json::to_value(MatchOutcome::InProgress).unwrap(), "in_progress");
+    }
+
+    #[test]
+    fn live_event_backup_matches_model() {
+        let raw: Value = serde_json::from_str(include_str!("../backup-data/live-event.json")).unwrap();
+
+        let typed: LiveEvent = serde_json::from_value(raw.clone()).unwrap();
+
+        assert_eq!(serde_json::to_value(&typed).unwrap(), raw);
+    }
+
+    #[test]
+    fn live_element_snapshot_matches_struct() {
+        let raw = json!({
+            "elements": [{
+                "id": 328,
+                "stats": {
+                    "minutes": 90,
+                    "goals_scored": 1,
+                    "assists": 1,
+                    "clean_sheets": 0,
+                    "goals_conceded": 1,
+                    "own_goals": 0,
+                    "penalties_saved": 0,
+                    "penalties_missed": 0,
+                    "yellow_cards": 0,
+                    "red_cards": 0,
+                    "saves": 0,
+                    "bonus": 3,
+                    "bps": 41,
+                    "total_points": 13,
+                    "influence": "62.4",
+                    "in_dreamteam": true
+                },
+                "explain": [{"fixture": 21, "stats": []}],
+                "modified": false
+            }]
+        });
+
+        let live: LiveEvent = serde_json::from_value(raw.clone()).unwrap();
+
+        let salah = &live.elements[0];
+        assert_eq!(salah.stats.goals_scored, Some(1));
+        assert_eq!(salah.stats.total_points, Some(13));
+        assert_eq!(salah.stats.extra["in_dreamteam"], true);
+        assert_eq!(salah.extra["modified"], false);
+        assert_eq!(serde_json::to_value(&live).unwrap(), raw);
+    }
+
+    #[test]
+    fn live_stats_allow_missing_and_negative_values() {
+        let stats: PlayerLiveStats = serde_json::from_value(json!({"minutes": 12, "bps": -3, "total_points": -1})).unwrap();
+
+        assert_eq!(stats.goals_scored, None);
+        assert_eq!(stats.bps, Some(-3));
+        assert_eq!(stats.total_points, Some(-1));
+        assert!(serde_json::from_value::<PlayerLiveStats>(json!({"goals_scored": -1})).is_err());
+    }
+
+    #[test]
+    fn entry_picks_snapshot_matches_struct() {
+        let raw = json!({
+            "active_chip": null,
+            "automatic_subs": [],
+            "entry_history": {"event": 4, "points": 61},
+            "picks": [
+                {"element": 328, "position": 1, "multiplier": 2, "is_captain": true, "is_vice_captain": false},
+                {"element": 351, "position": 12, "multiplier": 0, "is_captain": false, "is_vice_captain": false}
+            ]
+        });
+
+        let picks: EntryPicks = serde_json::from_value(raw.clone()).unwrap();
+
+        assert_eq!(picks.active_chip, None);
+        assert_eq!(picks.picks[0].multiplier, 2);
+        assert!(picks.picks[0].is_captain);
+        assert_eq!(picks.extra["entry_history"]["points"], 61);
+        assert_eq!(serde_json::to_value(&picks).unwrap(), raw);
+    }
+
+    #[test]
+    fn chip_is_parsed_from_active_chip() {
+        let picks = |chip: Value| {
+            serde_json::from_value::<EntryPicks>(json!({"active_chip": chip, "picks": []}))
+                .unwrap()
+                .chip()
+        };
+
+        assert_eq!(picks(json!("bboost")), Some(Chip::BenchBoost));
+        assert_eq!(picks(json!("3xc")), Some(Chip::TripleCaptain));
+        assert_eq!(picks(json!("freehit")), Some(Chip::FreeHit));
+        assert_eq!(picks(json!("wildcard")), Some(Chip::Wildcard));
+        assert_eq!(picks(json!("unreleased-chip")), None);
+        assert_eq!(picks(Value::Null), None);
+        assert_eq!(Chip::TripleCaptain.label(), "Triple Captain");
+    }
+
+    #[test]
+    fn malformed_player_is_rejected() {
+        let raw = json!({"id": "not-a-number", "web_name": "Broken"});
+        assert!(serde_json::from_value::<Player>(raw).is_err());
+    }
+}
@@ -0,0 +1,155 @@
+//! TLS certificate pinning for the upstream FPL API.
+//!
+//! [`pinned_client_config`] builds a rustls configuration that verifies every
+//! server against the Mozilla root store as usual and additionally requires the
+//! leaf certificate of the pinned host to have one of the given SHA-256
+//! fingerprints. Other hosts, such as the backup API, are only verified
+//! against the root store.
+
+use rustls::client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier};
+use rustls::{Certificate, ClientConfig, Error, OwnedTrustAnchor, RootCertStore, ServerName};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// SHA-256 digest of a DER-encoded certificate.
+pub type Fingerprint = [u8; 32];
+
+/// Parses a comma-separated list of hex SHA-256 fingerprints.
+///
+/// Colons and case are ignored, so both `openssl x509 -fingerprint -sha256`
+/// output (`AB:CD:...`) and plain hex are accepted.
+pub fn parse_fingerprints(value: &str) -> Result<Vec<Fingerprint>, String> {
+    let fingerprints = value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(parse_fingerprint)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if fingerprints.is_empty() {
+        return Err("expected at least one SHA-256 fingerprint".to_string());
+    }
+    Ok(fingerprints)
+}
+
+fn parse_fingerprint(entry: &str) -> Result<Fingerprint, String> {
+    let hex: String = entry.chars().filter(|c| *c != ':').collect();
+    if hex.len() != 64 || !hex.is_ascii() {
+        return Err(format!("`{}` is not a 64-digit hex SHA-256 fingerprint", entry));
+    }
+
+    let mut fingerprint = [0u8; 32];
+    for (byte, pair) in fingerprint.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        let pair = std::str::from_utf8(pair).map_err(|e| e.to_string())?;
+        *byte = u8::from_str_radix(pair, 16).map_err(|_| format!("`{}` contains non-hex characters", entry))?;
+    }
+    Ok(fingerprint)
+}
+
+/// Client configuration pinning the leaf certificate of `host` to `fingerprints`.
+pub fn pinned_client_config(host: &str, fingerprints: Vec<Fingerprint>) -> ClientConfig {
+    let mut roots = RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(anchor.subject, anchor.spki, anchor.name_constraints)
+    }));
+
+    let verifier = PinnedCertVerifier {
+        inner: WebPkiVerifier::new(roots, None),
+        host: host.to_ascii_lowercase(),
+        fingerprints,
+    };
+
+    ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_no_client_auth()
+}
+
+struct PinnedCertVerifier {
+    inner: WebPkiVerifier,
+    host: String,
+    fingerprints: Vec<Fingerprint>,
+}
+
+impl PinnedCertVerifier {
+    // Hosts other than the pinned one are accepted on chain validation alone
+    fn pin_matches(&self, server_name: &ServerName, end_entity: &Certificate) -> bool {
+        let pinned_host = matches!(server_name, ServerName::DnsName(name) if name.as_ref().eq_ignore_ascii_case(&self.host));
+        if !pinned_host {
+            return true;
+        }
+
+        let fingerprint: Fingerprint = Sha256::digest(&end_entity.0).into();
+        self.fingerprints.contains(&fingerprint)
+    }
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, Error> {
+        let verified = self
+            .inner
+            .verify_server_cert(end_entity, intermediates, server_name, scts, ocsp_response, now)?;
+
+        if self.pin_matches(server_name, end_entity) {
+            Ok(verified)
+        } else {
+            Err(Error::General(format!(
+                "certificate of {} does not match PINNED_FPL_CERT_SHA256",
+                self.host
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CERT: &[u8] = b"not really DER, but fingerprints do not care";
+
+    fn verifier(fingerprints: Vec<Fingerprint>) -> PinnedCertVerifier {
+        PinnedCertVerifier {
+            inner: WebPkiVerifier::new(RootCertStore::empty(), None),
+            host: "fantasy.premierleague.com".to_string(),
+            fingerprints,
+        }
+    }
+
+    fn server_name(host: &str) -> ServerName {
+        ServerName::try_from(host).unwrap()
+    }
+
+    #[test]
+    fn parse_fingerprints_accepts_openssl_and_plain_hex() {
+        let colons = "AB:".repeat(31) + "AB";
+        let plain = "0f".repeat(32);
+        let parsed = parse_fingerprints(&format!("{}, {}", colons, plain)).unwrap();
+        assert_eq!(parsed, vec![[0xab; 32], [0x0f; 32]]);
+    }
+
+    #[test]
+    fn parse_fingerprints_rejects_malformed_values() {
+        assert!(parse_fingerprints("").is_err());
+        assert!(parse_fingerprints("abcd").is_err());
+        assert!(parse_fingerprints(&"zz".repeat(32)).is_err());
+    }
+
+    #[test]
+    fn pin_is_enforced_only_for_the_pinned_host() {
+        let cert = Certificate(CERT.to_vec());
+        let matching: Fingerprint = Sha256::digest(CERT).into();
+
+        assert!(verifier(vec![[0; 32], matching]).pin_matches(&server_name("fantasy.premierleague.com"), &cert));
+        assert!(!verifier(vec![[0; 32]]).pin_matches(&server_name("FANTASY.premierleague.com"), &cert));
+        assert!(verifier(vec![[0; 32]]).pin_matches(&server_name("fpl-static-data.vercel.app"), &cert));
+    }
+}
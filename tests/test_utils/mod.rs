@@ -0,0 +1,16 @@
+//! Helpers shared by the integration tests.
+
+use std::sync::OnceLock;
+
+static TRACING: OnceLock<()> = OnceLock::new();
+
+/// Installs a log subscriber filtered by `RUST_LOG` (`off` when unset), once
+/// per test process however many tests call it.
+pub fn init_test_tracing() {
+    TRACING.get_or_init(|| {
+        tracing_subscriber::fmt()
+            .with_env_filter(std::env::var("RUST_LOG").unwrap_or_else(|_| "off".to_string()))
+            .try_init()
+            .ok();
+    });
+}